@@ -0,0 +1,103 @@
+use clap::{Parser, Subcommand};
+use flowrs_build::flow_diff::{diff_flows, merge_flows};
+use flowrs_build::flow_model::FlowModel;
+use serde_json::Value;
+use std::fs;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Arguments {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Shows the semantic difference between two flows.
+    Diff {
+        /// Old flow or flow project file.
+        old: String,
+        /// New flow or flow project file.
+        new: String,
+        /// Print the difference as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Merges two flows with their common ancestor.
+    Merge {
+        /// Flow or flow project file of the common ancestor.
+        base: String,
+        /// Our version. For flow projects, all fields besides the flow are taken from this file.
+        ours: String,
+        /// Their version.
+        theirs: String,
+        /// File to write the merged flow to. Prints to stdout if not set.
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+/// Reads a flow file. Flow project files are accepted as well, the document is returned alongside the flow.
+fn load_flow(path: &str) -> Result<(FlowModel, Value), anyhow::Error> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| anyhow::Error::msg(format!("Could not read '{}': {}", path, err)))?;
+    let document: Value = serde_json::from_str(&content)
+        .map_err(|err| anyhow::Error::msg(format!("Could not parse '{}': {}", path, err)))?;
+    let flow_value = document.get("flow").unwrap_or(&document).clone();
+    let flow: FlowModel = serde_json::from_value(flow_value)
+        .map_err(|err| anyhow::Error::msg(format!("'{}' does not contain a valid flow: {}", path, err)))?;
+    Ok((flow, document))
+}
+
+fn run(command: Command) -> Result<i32, anyhow::Error> {
+    match command {
+        Command::Diff { old, new, json } => {
+            let (old_flow, _) = load_flow(&old)?;
+            let (new_flow, _) = load_flow(&new)?;
+            let diff = diff_flows(&old_flow, &new_flow);
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print!("{}", diff);
+            }
+            Ok(if diff.is_empty() { 0 } else { 1 })
+        }
+        Command::Merge { base, ours, theirs, output } => {
+            let (base_flow, _) = load_flow(&base)?;
+            let (our_flow, mut our_document) = load_flow(&ours)?;
+            let (their_flow, _) = load_flow(&theirs)?;
+            let result = merge_flows(&base_flow, &our_flow, &their_flow)?;
+
+            let merged_flow = serde_json::to_value(&result.flow)?;
+            if our_document.get("flow").is_some() {
+                our_document["flow"] = merged_flow;
+            } else {
+                our_document = merged_flow;
+            }
+            let merged_content = serde_json::to_string_pretty(&our_document)?;
+
+            match output {
+                Some(output) => fs::write(&output, merged_content)?,
+                None => println!("{}", merged_content),
+            }
+
+            for conflict in &result.conflicts {
+                eprintln!("-> Conflict at {}", conflict);
+            }
+            Ok(if result.conflicts.is_empty() { 0 } else { 1 })
+        }
+    }
+}
+
+fn main() {
+    let args = Arguments::parse();
+
+    match run(args.command) {
+        Ok(code) => std::process::exit(code),
+        Err(err) => {
+            eprintln!("-> Error: {}", err);
+            std::process::exit(2);
+        }
+    }
+}
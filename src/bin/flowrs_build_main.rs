@@ -0,0 +1,231 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use flowrs_build::emitter_registry::EmitterRegistry;
+use flowrs_build::flow_project::{BuildTarget, FlowProject, FlowProjectManager};
+use flowrs_build::package_manager::PackageManager;
+use flowrs_build::runner::run_flow;
+use flowrs_build::service_config::{load_config, ServiceConfig};
+use std::fs;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Arguments {
+    /// Json config file (same format as for the service).
+    #[arg(short, long, default_value_t = f("config.json"))]
+    config_file: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Writes a project description with an empty flow.
+    New {
+        /// Name of the project.
+        name: String,
+        #[arg(long, default_value_t = f("1.0.0"))]
+        version: String,
+        /// Project description file. Defaults to [name].json.
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Generates the project crate from a project description.
+    Generate {
+        /// Project description file.
+        project_file: String,
+        /// Replace an existing project with the same name.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Checks packages, flow data and code generation of a project description without writing anything.
+    Validate {
+        /// Project description file.
+        project_file: String,
+    },
+    /// Builds a generated project.
+    Build {
+        /// Name of the project.
+        project_name: String,
+        #[arg(short, long, value_enum, default_value_t = Target::Native)]
+        target: Target,
+        #[arg(long)]
+        release: bool,
+    },
+    /// Builds a generated project for the desktop and runs it.
+    Run {
+        /// Name of the project.
+        project_name: String,
+        /// Number of workers to use.
+        #[arg(short, long, default_value_t = 1)]
+        workers: usize,
+        #[arg(long)]
+        release: bool,
+    },
+    /// Inspects the available packages.
+    Packages {
+        #[command(subcommand)]
+        command: PackagesCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PackagesCommand {
+    /// Lists name and version of all packages.
+    List,
+    /// Prints the description of a package.
+    Show {
+        /// Name of the package.
+        name: String,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Target {
+    Wasm,
+    Native,
+}
+
+impl From<Target> for BuildTarget {
+    fn from(target: Target) -> Self {
+        match target {
+            Target::Wasm => BuildTarget::Wasm,
+            Target::Native => BuildTarget::Native,
+        }
+    }
+}
+
+fn f(str: &str) -> String {
+    str.to_string()
+}
+
+fn load_project_file(project_file: &str) -> Result<FlowProject, anyhow::Error> {
+    let content = fs::read_to_string(project_file)
+        .map_err(|err| anyhow::Error::msg(format!("Could not read '{}': {}", project_file, err)))?;
+    serde_json::from_str(&content)
+        .map_err(|err| anyhow::Error::msg(format!("Could not parse '{}': {}", project_file, err)))
+}
+
+fn load_project_manager(config: &ServiceConfig) -> Result<FlowProjectManager, anyhow::Error> {
+    fs::create_dir_all(&config.flow_project_manager_config.project_folder)?;
+    let mut project_manager = FlowProjectManager::new(config.flow_project_manager_config.clone());
+    project_manager.load_projects()?;
+    Ok(project_manager)
+}
+
+fn run(args: Arguments) -> Result<i32, anyhow::Error> {
+    let config = load_config(&args.config_file)?;
+    let package_manager = PackageManager::new_from_folder(&config.flow_packages_folder);
+
+    match args.command {
+        Command::New { name, version, output } => {
+            let packages: Vec<serde_json::Value> = package_manager
+                .get_all_packages()
+                .iter()
+                .filter(|p| p.name != "built-in")
+                .map(|p| serde_json::json!({"name": p.name, "version": p.version}))
+                .collect();
+            let flow_project: FlowProject = serde_json::from_value(serde_json::json!({
+                "name": name,
+                "version": version,
+                "packages": packages,
+                "flow": {"nodes": {}, "connections": [], "data": {}}
+            }))?;
+
+            let output = output.unwrap_or(format!("{}.json", name));
+            fs::write(&output, serde_json::to_string_pretty(&flow_project)?)?;
+            println!("-> Created project description '{}'.", output);
+        }
+        Command::Generate { project_file, force } => {
+            let flow_project = load_project_file(&project_file)?;
+            let mut project_manager = load_project_manager(&config)?;
+
+            if project_manager.projects.contains_key(flow_project.name()) {
+                if !force {
+                    return Err(anyhow::Error::msg(format!(
+                        "Project '{}' already exists. Use --force to replace it.",
+                        flow_project.name()
+                    )));
+                }
+                project_manager.delete_flow_project(flow_project.name())?;
+            }
+
+            let (flow_project, warnings) = project_manager.create_flow_project(flow_project, &package_manager)?;
+            for warning in &warnings {
+                println!("-> Warning: {}", warning);
+            }
+            println!(
+                "-> Generated project '{}' in '{}'.",
+                flow_project.name(),
+                project_manager.get_project_folder(flow_project.name()).to_string_lossy()
+            );
+        }
+        Command::Validate { project_file } => {
+            let flow_project = load_project_file(&project_file)?;
+            let mut num_issues = 0;
+
+            for (package_name, issues) in package_manager.validate_all_packages() {
+                for issue in issues {
+                    println!("-> Package '{}': {}", package_name, issue);
+                    num_issues += 1;
+                }
+            }
+
+            let flow = flow_project.flow();
+            for issue in flow.validate_data(&package_manager).into_iter().chain(flow.validate_ports(&package_manager)) {
+                println!("-> Flow: {}", issue);
+                num_issues += 1;
+            }
+
+            let code = EmitterRegistry::new()
+                .create_emitter(flow_project.emitter())
+                .and_then(|emitter| emitter.emit_flow_code(flow_project.flow(), &package_manager));
+            if let Err(err) = code {
+                println!("-> Code generation: {}", err);
+                num_issues += 1;
+            }
+
+            println!("-> Found {} issue(s).", num_issues);
+            return Ok(if num_issues > 0 { 1 } else { 0 });
+        }
+        Command::Build { project_name, target, release } => {
+            let project_manager = load_project_manager(&config)?;
+            println!("-> Build project '{}'.", project_name);
+            let artifact = project_manager.build_flow_project(&project_name, target.into(), release)?;
+            println!("-> Built '{}'.", artifact.to_string_lossy());
+        }
+        Command::Run { project_name, workers, release } => {
+            let project_manager = load_project_manager(&config)?;
+            println!("-> Build project '{}'.", project_name);
+            let artifact = project_manager.build_flow_project(&project_name, BuildTarget::Native, release)?;
+            let result = run_flow(&artifact.to_string_lossy(), workers)?;
+            println!("-> Flow execution result: {}", result);
+        }
+        Command::Packages { command: PackagesCommand::List } => {
+            let mut packages = package_manager.get_all_packages();
+            packages.sort_by(|a, b| a.name.cmp(&b.name));
+            for package in packages {
+                println!("{} {}", package.name, package.version);
+            }
+        }
+        Command::Packages { command: PackagesCommand::Show { name } } => {
+            let package = package_manager
+                .get_package(&name)
+                .ok_or_else(|| anyhow::Error::msg(format!("Package '{}' does not exist.", name)))?;
+            println!("{}", serde_json::to_string_pretty(package)?);
+        }
+    }
+
+    Ok(0)
+}
+
+fn main() {
+    let args = Arguments::parse();
+
+    match run(args) {
+        Ok(code) => std::process::exit(code),
+        Err(err) => {
+            eprintln!("-> Error: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use clap::Parser;
+use flowrs_build::package_manager::PackageManager;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Arguments {
+    /// Folder with the package descriptions to check.
+    #[arg(short, long, default_value_t = f("flow-packages"))]
+    packages_folder: String,
+}
+
+fn f(str: &str) -> String {
+    str.to_string()
+}
+
+fn main() {
+    let args = Arguments::parse();
+
+    println!("-> Check packages in '{}'.", args.packages_folder);
+    let package_manager = PackageManager::new_from_folder(&args.packages_folder);
+
+    let mut package_issues: Vec<_> = package_manager.validate_all_packages().into_iter().collect();
+    package_issues.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let num_issues: usize = package_issues.iter().map(|(_, issues)| issues.len()).sum();
+    for (package_name, issues) in &package_issues {
+        println!("-> Package '{}':", package_name);
+        for issue in issues {
+            println!("   {}", issue);
+        }
+    }
+
+    let num_load_errors = package_manager
+        .get_load_status()
+        .iter()
+        .filter(|status| status.error.is_some())
+        .count();
+
+    println!("-> Found {} issue(s) and {} file(s) that could not be loaded.", num_issues, num_load_errors);
+
+    if num_issues > 0 || num_load_errors > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,73 @@
+use clap::{Parser, Subcommand};
+use flowrs_build::flow_project::FlowProjectManager;
+use flowrs_build::package_manager::PackageManager;
+use flowrs_build::service_config::load_config;
+use std::fs;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Arguments {
+    /// Json config file (same format as for the service).
+    #[arg(short, long, default_value_t = f("config.json"))]
+    config_file: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Writes a project and its package descriptions to a tar archive.
+    Export {
+        /// Name of the project.
+        project_name: String,
+        /// Archive file. Defaults to [project_name].tar.
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Recreates a project from a tar archive.
+    Import {
+        /// Archive file.
+        archive: String,
+    },
+}
+
+fn f(str: &str) -> String {
+    str.to_string()
+}
+
+fn run(args: Arguments) -> Result<(), anyhow::Error> {
+    let config = load_config(&args.config_file)?;
+    let package_manager = PackageManager::new_from_folder(&config.flow_packages_folder);
+    let mut project_manager = FlowProjectManager::new(config.flow_project_manager_config);
+    project_manager.load_projects()?;
+
+    match args.command {
+        Command::Export { project_name, output } => {
+            let archive = project_manager
+                .export_flow_project(&project_name, &package_manager)?
+                .ok_or_else(|| anyhow::Error::msg(format!("Project '{}' does not exist.", project_name)))?;
+            let output = output.unwrap_or(format!("{}.tar", project_name));
+            fs::write(&output, archive)?;
+            println!("-> Exported project '{}' to '{}'.", project_name, output);
+        }
+        Command::Import { archive } => {
+            let import = project_manager.import_flow_project(&fs::read(&archive)?, &package_manager)?;
+            for warning in &import.warnings {
+                println!("-> Warning: {}", warning);
+            }
+            println!("-> Imported project '{}'.", import.project.name());
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args = Arguments::parse();
+
+    if let Err(err) = run(args) {
+        eprintln!("-> Error: {}", err);
+        std::process::exit(1);
+    }
+}
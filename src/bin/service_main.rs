@@ -1,9 +1,9 @@
 use axum::{
-    body::{Body, StreamBody},
-    extract::{Path, State},
+    body::{Body, Bytes, StreamBody},
+    extract::{rejection::JsonRejection, Path, Query, State},
     http::{header, Response, StatusCode},
     response::IntoResponse,
-    routing::{get, post},
+    routing::{get, post, put},
     Json, Router,
 };
 use tokio_util::io::ReaderStream;
@@ -11,19 +11,20 @@ use tokio::sync::broadcast;
 
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
-use std::{path::{PathBuf}, process::Command};
-use std::fs::File;
-use std::io::Read;
 use std::fs;
 
 use clap::Parser;
+use serde::Deserialize;
 
 use flowrs_build::{
-    flow_project::{FlowProject, FlowProjectManager, FlowProjectManagerConfig},
+    emitter_registry::EmitterInfo,
+    flow_model::FlowLayout,
+    flow_project::{build_project_folder, BuildTarget, FlowProject, FlowProjectImport, FlowProjectManager, FlowProjectSources},
     package::Package,
-    package_manager::PackageManager,
+    package_manager::{PackageFileStatus, PackageManager, TypeDescription, TypeQuery},
+    schema::{get_schema, SCHEMA_KINDS},
+    service_config::load_config,
 };
-use serde::{Deserialize, Serialize};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -33,52 +34,35 @@ struct Arguments {
     config_file: String,
 }
 
+/// State of the handlers that need both managers.
+type ProjectState = (Arc<Mutex<FlowProjectManager>>, Arc<Mutex<PackageManager>>);
+
 fn f(str: &str) -> String {
     str.to_string()
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct ServiceConfig {
-    #[serde(default = "flow_project_manager_config_default")]
-    flow_project_manager_config: FlowProjectManagerConfig,
-
-    #[serde(default = "flow_packages_folder_default")]
-    flow_packages_folder: String,  
-}
-
-fn flow_project_manager_config_default() -> FlowProjectManagerConfig {
-    FlowProjectManagerConfig::default()
-}
-
-fn flow_packages_folder_default() -> String {
-    "flow-packages".to_string()
-}
-
-impl Default for ServiceConfig {
-    fn default() -> Self {
-        Self {
-            flow_project_manager_config: flow_project_manager_config_default(),
-            flow_packages_folder: flow_packages_folder_default()
+async fn reload_packages_periodically(package_manager: Arc<Mutex<PackageManager>>, interval_secs: u64) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+    loop {
+        interval.tick().await;
+
+        // Read the files without holding the lock, so requests are not blocked by file I/O.
+        let state = match package_manager.lock().unwrap().get_package_folder_state() {
+            Some(state) => state,
+            None => return,
+        };
+        let contents = match tokio::task::spawn_blocking(move || state.read()).await {
+            Ok(Some(contents)) => contents,
+            _ => continue,
+        };
+
+        let changed_packages = package_manager.lock().unwrap().apply_package_folder(contents);
+        for package_name in changed_packages {
+            println!("-> Reloaded package '{}'.", package_name);
         }
     }
 }
 
-fn load_config(config_path: &str) -> ServiceConfig {
-    
-    if std::path::Path::new(config_path).exists() {
-        // Read and deserialize the existing config.json file
-        let mut file = File::open(config_path).expect(&format!("Failed to open {}", config_path).as_str());
-        let mut config_content = String::new();
-        file.read_to_string(&mut config_content)
-            .expect(&format!("Failed to read {}", config_path).as_str());
-        serde_json::from_str(&config_content).expect(&format!("Failed to deserialize from {}", config_path).as_str())
-    } else {
-        // If the file doesn't exist, create a new FlowProjectManagerConfig with default values.
-        println!("-> Could not read config file '{}'. Creating default config.", config_path);
-        ServiceConfig::default()
-    }
-}
-
 async fn handle_shutdown_signal(
     stopper: tokio::sync::broadcast::Sender<()>,
 ) -> std::io::Result<()> {
@@ -97,10 +81,19 @@ async fn main() {
 
     let args = Arguments::parse();
 
-    let config = load_config(&args.config_file.as_str());
+    let config = match load_config(&args.config_file) {
+        Ok(config) => config,
+        Err(err) => {
+            println!("-> Failed to load config. Reason: {}", err);
+            return;
+        }
+    };
 
     // Setup package manager
     let package_manager = Arc::new(Mutex::new(PackageManager::new_from_folder(&config.flow_packages_folder)));
+    if config.package_reload_interval_secs > 0 {
+        tokio::spawn(reload_packages_periodically(package_manager.clone(), config.package_reload_interval_secs));
+    }
 
     // Setup project manager. 
     let project_folder = config.flow_project_manager_config.project_folder.clone();
@@ -117,15 +110,31 @@ async fn main() {
     }
 
     let app = Router::new()
+        .route("/schemas/:kind", get(get_schema_by_kind))
+        .route("/schemas/", get(get_schema_kinds))
         .route("/build/:project_name", get(build_package))
         .route("/file/:project_name/:file_name", get(get_file))
         .with_state(project_manager.clone())
-        .route("/packages/:package_name", get(get_package_by_name))
+        .route("/packages/_status", get(get_package_load_status))
+        .route(
+            "/packages/:package_name",
+            get(get_package_by_name)
+                .post(create_package)
+                .put(replace_package)
+                .delete(delete_package),
+        )
         .route("/packages/", get(get_all_packages))
+        .route("/types/:type_name", get(get_type_by_name))
+        .route("/types/", get(find_types))
         .with_state(package_manager.clone())
-        //.route("/projects/:project_name", get(get_project_by_name))
+        .route("/projects/:project_name", get(get_project_by_name))
+        .route("/projects/:project_name/layout", put(update_project_layout))
+        .route("/projects/:project_name/export", get(export_project))
+        .route("/projects/import", post(import_project))
         .route("/projects/", get(get_all_projects))
         .route("/projects/", post(create_project))
+        .route("/generate", post(generate_project_sources))
+        .route("/emitters/", get(get_all_emitters))
         .with_state((project_manager.clone(), package_manager.clone()));
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
@@ -154,7 +163,7 @@ async fn get_file(
         .projects
         .get(&project_name)
         .cloned();
-    if let None = project {
+    if project.is_none() {
         let error_message = format!(
             "The Project {} does not exist in the Project Manager",
             project_name
@@ -162,8 +171,7 @@ async fn get_file(
         eprintln!("{}", error_message);
         return Err((StatusCode::INTERNAL_SERVER_ERROR, error_message.to_string()));
     }
-    let project_path = PathBuf::from(format!("./flow-projects/{}", project_name));
-    let target_dir = project_path.join("pkg");
+    let target_dir = project_manager.lock().unwrap().get_project_folder(&project_name).join("pkg");
 
     if !target_dir.exists() {
         let error_message = "The target dir of the generated WASM file cannot be found.";
@@ -171,7 +179,6 @@ async fn get_file(
         return Err((StatusCode::INTERNAL_SERVER_ERROR, error_message.to_string()));
     }
 
-    let file_name = file_name;
     let file_path = target_dir.join(file_name.clone());
     let file = tokio::fs::File::open(file_path.clone())
         .await
@@ -202,87 +209,54 @@ async fn build_package(
     State(project_manager): State<Arc<Mutex<FlowProjectManager>>>,
     Path(project_name): Path<String>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let project = project_manager
-        .lock()
-        .unwrap()
-        .projects
-        .get(&project_name)
-        .cloned();
-
-    if let None = project {
-        let error_message = format!(
-            "The Project {} does not exist in the Project Manager",
-            project_name
-        );
-        eprintln!("{}", error_message);
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, error_message.to_string()));
-    }
-
-    let project_path = PathBuf::from(format!("./flow-projects/{}", project_name));
-
-    if !project_path.exists() {
-        let error_message = "The specified project directory does not exist.";
-        eprintln!("{}", error_message);
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, error_message.to_string()));
-    }
-
-    if let Err(err) = std::env::set_current_dir(&project_path) {
-        let error_message = format!("Failed to change the working directory: {}", err);
-        eprintln!("{} {}", error_message, err);
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, error_message.to_string()));
-    }
-
-    let wasm_pack_build = Command::new("wasm-pack")
-        .args(&["build", "--target", "web"])
-        .output();
-
-    if let Err(err) = wasm_pack_build {
-        let error_message = format!("Couldn't build the project: {}", err);
-        eprintln!("{} {}", error_message, err);
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, error_message.to_string()));
-    }
-
-    let output = wasm_pack_build.unwrap();
-
-    if !output.status.success() {
-        eprintln!("Failed to build the project for WebAssembly.");
-        if let Some(stderr) = String::from_utf8_lossy(&output.stderr)
-            .trim()
-            .to_string()
-            .split('\n')
-            .next()
-        {
-            eprintln!("Error: {}", stderr);
+    // Only look up the project folder under the lock, so the build does not block other requests.
+    let project_folder = {
+        let project_manager = project_manager.lock().unwrap();
+        if !project_manager.projects.contains_key(&project_name) {
+            return Err((StatusCode::NOT_FOUND, format!("Project '{}' does not exist.", project_name)));
         }
-    }
-
-    println!("Project built successfully!");
-    let target_dir = project_path.join("pkg");
+        project_manager.get_project_folder(&project_name)
+    };
 
-    if !target_dir.exists() {
-        let error_message = "The target dir of the generated WASM file cannot be found.";
-        eprintln!("{}", error_message);
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, error_message.to_string()));
-    }
+    let build_project_name = project_name.clone();
+    let wasm_file_path = tokio::task::spawn_blocking(move || {
+        build_project_folder(&project_folder, &build_project_name, BuildTarget::Wasm, true)
+    })
+    .await
+    .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+    .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
 
-    let file_name = format!("{}_bg.wasm", project_name);
-    let wasm_file_path = target_dir.join(file_name.clone());
-    let wasm_file = tokio::fs::File::open(wasm_file_path)
+    let file_name = wasm_file_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let wasm_file = tokio::fs::File::open(&wasm_file_path)
         .await
-        .expect("Failed to open Wasm file");
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to open Wasm file: {}", err)))?;
     let stream = ReaderStream::new(wasm_file);
     let body = StreamBody::new(stream);
     let headers = [
         (header::CONTENT_TYPE, "application/wasm".to_string()),
         (
             header::CONTENT_DISPOSITION,
-            format!("attachment; filename=\"{:?}\"", file_name),
+            format!("attachment; filename=\"{}\"", file_name),
         ),
     ];
 
     Ok((headers, body))
 }
 
+async fn get_schema_kinds() -> Json<Vec<String>> {
+    Json(SCHEMA_KINDS.iter().map(|kind| kind.to_string()).collect())
+}
+
+async fn get_schema_by_kind(
+    Path(kind): Path<String>,
+) -> Result<Json<schemars::schema::RootSchema>, StatusCode> {
+    if let Some(schema) = get_schema(&kind) {
+        return Ok(Json(schema));
+    }
+
+    Err(StatusCode::NOT_FOUND)
+}
+
 async fn get_all_packages(
     State(package_manager): State<Arc<Mutex<PackageManager>>>,
 ) -> Json<Vec<Package>> {
@@ -300,11 +274,95 @@ async fn get_package_by_name(
     Err(StatusCode::NOT_FOUND)
 }
 
+async fn get_package_load_status(
+    State(package_manager): State<Arc<Mutex<PackageManager>>>,
+) -> Json<Vec<PackageFileStatus>> {
+    Json(package_manager.lock().unwrap().get_load_status())
+}
+
+/// I/O errors are server errors, all other package errors (invalid names, the built-in package) are client errors.
+fn package_error_response(err: anyhow::Error) -> (StatusCode, String) {
+    if err.downcast_ref::<std::io::Error>().is_some() {
+        (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+    } else {
+        (StatusCode::BAD_REQUEST, err.to_string())
+    }
+}
+
+/// Packages that do not match the `Package` schema are bad requests.
+fn package_body(body: Result<Json<Package>, JsonRejection>) -> Result<Package, (StatusCode, String)> {
+    body.map(|Json(package)| package)
+        .map_err(|rejection| (StatusCode::BAD_REQUEST, rejection.body_text()))
+}
+
+async fn create_package(
+    Path(package_name): Path<String>,
+    State(package_manager): State<Arc<Mutex<PackageManager>>>,
+    body: Result<Json<Package>, JsonRejection>,
+) -> Result<(StatusCode, Json<Package>), (StatusCode, String)> {
+    let package = package_body(body)?;
+    if package.name != package_name {
+        return Err((StatusCode::BAD_REQUEST, format!("Package name '{}' does not match '{}'.", package.name, package_name)));
+    }
+
+    let mut pm = package_manager.lock().unwrap();
+    if pm.get_package(&package_name).is_some() {
+        return Err((StatusCode::CONFLICT, format!("Package '{}' already exists.", package_name)));
+    }
+
+    match pm.save_package(package.clone()) {
+        Ok(()) => Ok((StatusCode::CREATED, Json(package))),
+        Err(err) => Err(package_error_response(err)),
+    }
+}
+
+async fn replace_package(
+    Path(package_name): Path<String>,
+    State(package_manager): State<Arc<Mutex<PackageManager>>>,
+    body: Result<Json<Package>, JsonRejection>,
+) -> Result<Json<Package>, (StatusCode, String)> {
+    let package = package_body(body)?;
+    if package.name != package_name {
+        return Err((StatusCode::BAD_REQUEST, format!("Package name '{}' does not match '{}'.", package.name, package_name)));
+    }
+
+    match package_manager.lock().unwrap().save_package(package.clone()) {
+        Ok(()) => Ok(Json(package)),
+        Err(err) => Err(package_error_response(err)),
+    }
+}
+
+async fn delete_package(
+    Path(package_name): Path<String>,
+    State(package_manager): State<Arc<Mutex<PackageManager>>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match package_manager.lock().unwrap().remove_package(&package_name) {
+        Ok(Some(_)) => Ok(StatusCode::NO_CONTENT),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("Package '{}' does not exist.", package_name))),
+        Err(err) => Err(package_error_response(err)),
+    }
+}
+
+async fn find_types(
+    Query(query): Query<TypeQuery>,
+    State(package_manager): State<Arc<Mutex<PackageManager>>>,
+) -> Json<Vec<TypeDescription>> {
+    Json(package_manager.lock().unwrap().find_types(&query))
+}
+
+async fn get_type_by_name(
+    Path(type_name): Path<String>,
+    State(package_manager): State<Arc<Mutex<PackageManager>>>,
+) -> Result<Json<TypeDescription>, StatusCode> {
+    if let Some(type_desc) = package_manager.lock().unwrap().get_type_description(&type_name) {
+        return Ok(Json(type_desc));
+    }
+
+    Err(StatusCode::NOT_FOUND)
+}
+
 async fn get_all_projects(
-    State((project_manager, package_manager)): State<(
-        Arc<Mutex<FlowProjectManager>>,
-        Arc<Mutex<PackageManager>>,
-    )>,
+    State((project_manager, _)): State<ProjectState>,
 ) -> Json<Vec<FlowProject>> {
     let all_projects: Vec<FlowProject> = project_manager
         .lock()
@@ -316,11 +374,98 @@ async fn get_all_projects(
     Json(all_projects)
 }
 
+async fn get_all_emitters(
+    State((project_manager, _)): State<ProjectState>,
+) -> Json<Vec<EmitterInfo>> {
+    Json(project_manager.lock().unwrap().get_emitter_infos())
+}
+
+async fn get_project_by_name(
+    Path(project_name): Path<String>,
+    State((project_manager, _)): State<ProjectState>,
+) -> Result<Json<FlowProject>, StatusCode> {
+    if let Some(project) = project_manager.lock().unwrap().projects.get(&project_name) {
+        return Ok(Json(project.clone()));
+    }
+
+    Err(StatusCode::NOT_FOUND)
+}
+
+async fn update_project_layout(
+    Path(project_name): Path<String>,
+    State((project_manager, _)): State<ProjectState>,
+    Json(layout): Json<FlowLayout>,
+) -> Result<Json<FlowProject>, (StatusCode, String)> {
+    match project_manager.lock().unwrap().update_flow_project_layout(&project_name, layout) {
+        Ok(Some(project)) => Ok(Json(project)),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("Project '{}' does not exist.", project_name))),
+        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+    }
+}
+
+async fn export_project(
+    Path(project_name): Path<String>,
+    State((project_manager, package_manager)): State<ProjectState>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let archive = project_manager
+        .lock()
+        .unwrap()
+        .export_flow_project(&project_name, &package_manager.lock().unwrap())
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, format!("Project '{}' does not exist.", project_name)))?;
+
+    let headers = [
+        (header::CONTENT_TYPE, "application/x-tar".to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.tar\"", project_name),
+        ),
+    ];
+
+    Ok((headers, archive))
+}
+
+async fn import_project(
+    State((project_manager, package_manager)): State<ProjectState>,
+    archive: Bytes,
+) -> Result<(StatusCode, Json<FlowProjectImport>), (StatusCode, String)> {
+    match project_manager
+        .lock()
+        .unwrap()
+        .import_flow_project(&archive, &package_manager.lock().unwrap())
+    {
+        Ok(import) => {
+            for warning in &import.warnings {
+                println!("-> Import of project '{}': {}", import.project.name(), warning);
+            }
+            Ok((StatusCode::CREATED, Json(import)))
+        }
+        Err(err) => Err((StatusCode::BAD_REQUEST, err.to_string())),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateQuery {
+    /// Format the generated lib.rs, with the built-in formatter (prettyplease) or with rustfmt if the config sets `use_rust_fmt`.
+    #[serde(default)]
+    format: bool,
+}
+
+async fn generate_project_sources(
+    Query(query): Query<GenerateQuery>,
+    State((project_manager, package_manager)): State<ProjectState>,
+    Json(flow_project): Json<FlowProject>,
+) -> Result<Json<FlowProjectSources>, (StatusCode, String)> {
+    project_manager
+        .lock()
+        .unwrap()
+        .generate_flow_project_sources(&flow_project, &package_manager.lock().unwrap(), query.format)
+        .map(Json)
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))
+}
+
 async fn create_project(
-    State((project_manager, package_manager)): State<(
-        Arc<Mutex<FlowProjectManager>>,
-        Arc<Mutex<PackageManager>>,
-    )>,
+    State((project_manager, package_manager)): State<ProjectState>,
     Json(flow_project): Json<FlowProject>,
 ) -> Result<Response<Body>, StatusCode> {
     match project_manager
@@ -328,7 +473,11 @@ async fn create_project(
         .unwrap()
         .create_flow_project(flow_project, &package_manager.lock().unwrap())
     {
-        Ok(flow_project) => {
+        Ok((flow_project, warnings)) => {
+            for warning in &warnings {
+                println!("-> Project '{}': {}", flow_project.name(), warning);
+            }
+
             // Return a success response with the created object in the body
             let response = Response::builder()
                 .status(StatusCode::CREATED)
@@ -0,0 +1,400 @@
+use anyhow::{Error, Result};
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::package_manager::PackageManager;
+
+/// Converts flow data into Rust expressions at build time, so generated code does not need serde_json.
+///
+/// Supported are primitives, `&str`, tuples, arrays, `&[T]`, `Option<T>`, with `allow_alloc` also
+/// `String`, `Vec<T>` and `Box<T>`, and struct types whose package declares their public `fields`.
+/// Everything else (enums, types with private fields like `Duration`, maps) is rejected.
+pub struct DataLiteralEmitter<'a> {
+    pub pack_man: &'a PackageManager,
+    pub allow_alloc: bool,
+}
+
+/// Error for types that cannot be emitted as a literal at all, as opposed to values that do not fit their type.
+/// Callers can check for it with `Error::is` and parse such data at runtime instead.
+#[derive(Debug)]
+pub struct UnsupportedType(String);
+
+impl fmt::Display for UnsupportedType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UnsupportedType {}
+
+/// A path type being emitted, e.g. `my_crate::Config<u32>` with name "my_crate::Config" and arguments `[u32]`.
+struct PathType<'t> {
+    data_type: &'t syn::Type,
+    path: &'t syn::Path,
+    name: String,
+    arguments: Vec<syn::Type>,
+}
+
+impl<'a> DataLiteralEmitter<'a> {
+    /// Emits `value` as an expression of type `data_type`. `path` is the JSON path of `value` used in error messages.
+    pub fn emit_literal(&self, value: &Value, data_type: &syn::Type, path: &str) -> Result<TokenStream, Error> {
+        self.emit_literal_rec(value, data_type, &HashMap::new(), path)
+    }
+
+    fn emit_literal_rec(
+        &self,
+        value: &Value,
+        data_type: &syn::Type,
+        type_parameters: &HashMap<String, syn::Type>,
+        path: &str,
+    ) -> Result<TokenStream, Error> {
+        match data_type {
+            syn::Type::Paren(paren) => self.emit_literal_rec(value, &paren.elem, type_parameters, path),
+            syn::Type::Group(group) => self.emit_literal_rec(value, &group.elem, type_parameters, path),
+
+            syn::Type::Tuple(tuple) => {
+                if tuple.elems.is_empty() {
+                    return Ok(quote!(()));
+                }
+                let items = self.get_items(value, Some(tuple.elems.len()), data_type, path)?;
+                let item_codes = items
+                    .iter()
+                    .zip(tuple.elems.iter())
+                    .enumerate()
+                    .map(|(index, (item, elem))| self.emit_literal_rec(item, elem, type_parameters, &format!("{}[{}]", path, index)))
+                    .collect::<Result<Vec<TokenStream>, Error>>()?;
+                Ok(quote!((#(#item_codes,)*)))
+            }
+
+            syn::Type::Array(array) => {
+                let length = match &array.len {
+                    syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(length), .. }) => length.base10_parse::<usize>()?,
+                    _ => return Err(self.unsupported(data_type, path, "array lengths must be integer literals")),
+                };
+                let item_codes = self.emit_items(value, Some(length), &array.elem, data_type, type_parameters, path)?;
+                Ok(quote!([#(#item_codes),*]))
+            }
+
+            syn::Type::Reference(reference) => match reference.elem.as_ref() {
+                syn::Type::Path(type_path) if type_path.path.is_ident("str") => match value {
+                    Value::String(text) => {
+                        let literal = Literal::string(text);
+                        Ok(quote!(#literal))
+                    }
+                    _ => Err(self.mismatch(value, data_type, path)),
+                },
+                syn::Type::Slice(slice) => {
+                    let item_codes = self.emit_items(value, Option::None, &slice.elem, data_type, type_parameters, path)?;
+                    Ok(quote!(&[#(#item_codes),*]))
+                }
+                _ => Err(self.unsupported(data_type, path, "only &str and &[T] references can be emitted")),
+            },
+
+            syn::Type::Path(type_path) if type_path.qself.is_none() => {
+                if let Some(ident) = type_path.path.get_ident() {
+                    if let Some(resolved_type) = type_parameters.get(&ident.to_string()) {
+                        return self.emit_literal_rec(value, resolved_type, &HashMap::new(), path);
+                    }
+                }
+                self.emit_path_literal(value, data_type, &type_path.path, type_parameters, path)
+            }
+
+            _ => Err(self.unsupported(data_type, path, "type cannot be emitted as a literal")),
+        }
+    }
+
+    fn emit_path_literal(
+        &self,
+        value: &Value,
+        data_type: &syn::Type,
+        type_path: &syn::Path,
+        type_parameters: &HashMap<String, syn::Type>,
+        path: &str,
+    ) -> Result<TokenStream, Error> {
+        let path_type = PathType {
+            data_type,
+            path: type_path,
+            name: type_path.segments.iter().map(|s| s.ident.to_string()).collect::<Vec<String>>().join("::"),
+            arguments: self.get_type_arguments(type_path),
+        };
+        let type_name = &path_type.name;
+        let type_arguments = &path_type.arguments;
+
+        match (type_name.as_str(), value) {
+            ("i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize", Value::Number(n)) => {
+                let n: i128 = match (n.as_i64(), n.as_u64()) {
+                    (Some(i), _) => i.into(),
+                    (_, Some(u)) => u.into(),
+                    _ => return Err(self.mismatch(value, data_type, path)),
+                };
+                if !integer_fits(n, type_name) {
+                    return Err(Error::msg(format!("{}: {} is out of range for '{}'.", path, n, type_name)));
+                }
+                let literal = syn::LitInt::new(&format!("{}{}", n, type_name), Span::call_site());
+                Ok(quote!(#literal))
+            }
+
+            ("f32" | "f64", Value::Number(n)) => {
+                let n = n.as_f64().ok_or_else(|| self.mismatch(value, data_type, path))?;
+                if !float_fits(n, type_name) {
+                    return Err(Error::msg(format!("{}: {} is out of range for '{}'.", path, n, type_name)));
+                }
+                let literal = syn::LitFloat::new(&format!("{:?}{}", n, type_name), Span::call_site());
+                Ok(quote!(#literal))
+            }
+
+            ("bool", Value::Bool(b)) => Ok(quote!(#b)),
+
+            ("char", Value::String(text)) if text.chars().count() == 1 => {
+                let literal = Literal::character(text.chars().next().unwrap_or_default());
+                Ok(quote!(#literal))
+            }
+
+            ("Option" | "std::option::Option" | "core::option::Option", _) => {
+                let element = self.get_single_type_argument(type_arguments, data_type, path)?;
+                if value.is_null() {
+                    Ok(quote!(None))
+                } else {
+                    let element_code = self.emit_literal_rec(value, element, type_parameters, path)?;
+                    Ok(quote!(Some(#element_code)))
+                }
+            }
+
+            ("String" | "std::string::String" | "alloc::string::String", Value::String(text)) if self.allow_alloc => {
+                let literal = Literal::string(text);
+                Ok(quote!(String::from(#literal)))
+            }
+
+            ("Vec" | "std::vec::Vec" | "alloc::vec::Vec", _) if self.allow_alloc => {
+                let element = self.get_single_type_argument(type_arguments, data_type, path)?;
+                let item_codes = self.emit_items(value, Option::None, element, data_type, type_parameters, path)?;
+                Ok(quote!(vec![#(#item_codes),*]))
+            }
+
+            ("Box" | "std::boxed::Box" | "alloc::boxed::Box", _) if self.allow_alloc => {
+                let element = self.get_single_type_argument(type_arguments, data_type, path)?;
+                let element_code = self.emit_literal_rec(value, element, type_parameters, path)?;
+                Ok(quote!(Box::new(#element_code)))
+            }
+
+            ("String" | "std::string::String" | "alloc::string::String" | "Vec" | "std::vec::Vec" | "alloc::vec::Vec"
+                | "Box" | "std::boxed::Box" | "alloc::boxed::Box", _) => {
+                if self.allow_alloc {
+                    Err(self.mismatch(value, data_type, path))
+                } else {
+                    Err(self.unsupported(data_type, path, "heap allocated values are not available without alloc"))
+                }
+            }
+
+            ("i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize"
+                | "f32" | "f64" | "bool" | "char", _) => Err(self.mismatch(value, data_type, path)),
+
+            _ => self.emit_struct_literal(value, &path_type, type_parameters, path),
+        }
+    }
+
+    fn emit_struct_literal(
+        &self,
+        value: &Value,
+        path_type: &PathType,
+        type_parameters: &HashMap<String, syn::Type>,
+        path: &str,
+    ) -> Result<TokenStream, Error> {
+        let (data_type, type_name) = (path_type.data_type, &path_type.name);
+        let t = self
+            .pack_man
+            .get_type(type_name)
+            .ok_or_else(|| Error::new(UnsupportedType(format!("{}: Unknown type '{}'.", path, type_name))))?;
+        let fields = t
+            .fields
+            .as_ref()
+            .ok_or_else(|| self.unsupported(data_type, path, "the type does not declare its fields"))?;
+        let object = value.as_object().ok_or_else(|| self.mismatch(value, data_type, path))?;
+
+        // Type arguments may be type parameters of the enclosing struct.
+        let field_type_parameters: HashMap<String, syn::Type> = t
+            .type_parameters
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .zip(path_type.arguments.iter().map(|arg| self.resolve_type_parameter(arg, type_parameters)))
+            .collect();
+
+        let mut field_codes = Vec::new();
+        for field in fields {
+            let field_ident: syn::Ident = syn::parse_str(&field.name)
+                .map_err(|err| Error::msg(format!("Invalid field name '{}' of type '{}': {}", field.name, type_name, err)))?;
+            let field_type: syn::Type = syn::parse_str(&field.field_type)
+                .map_err(|err| Error::msg(format!("Invalid type '{}' of field '{}.{}': {}", field.field_type, type_name, field.name, err)))?;
+            let field_path = format!("{}.{}", path, field.name);
+
+            let field_code = self.emit_literal_rec(object.get(&field.name).unwrap_or(&Value::Null), &field_type, &field_type_parameters, &field_path)?;
+            field_codes.push(quote!(#field_ident: #field_code));
+        }
+
+        // Type arguments are inferred from the annotated type of the local the literal is assigned to.
+        let mut struct_path = path_type.path.clone();
+        for segment in struct_path.segments.iter_mut() {
+            segment.arguments = syn::PathArguments::None;
+        }
+
+        Ok(quote!(#struct_path { #(#field_codes),* }))
+    }
+
+    fn emit_items(
+        &self,
+        value: &Value,
+        length: Option<usize>,
+        element: &syn::Type,
+        data_type: &syn::Type,
+        type_parameters: &HashMap<String, syn::Type>,
+        path: &str,
+    ) -> Result<Vec<TokenStream>, Error> {
+        self.get_items(value, length, data_type, path)?
+            .iter()
+            .enumerate()
+            .map(|(index, item)| self.emit_literal_rec(item, element, type_parameters, &format!("{}[{}]", path, index)))
+            .collect()
+    }
+
+    fn get_items<'v>(&self, value: &'v Value, length: Option<usize>, data_type: &syn::Type, path: &str) -> Result<&'v Vec<Value>, Error> {
+        match value {
+            Value::Array(items) if length.is_none() || length == Some(items.len()) => Ok(items),
+            _ => Err(self.mismatch(value, data_type, path)),
+        }
+    }
+
+    fn get_type_arguments(&self, type_path: &syn::Path) -> Vec<syn::Type> {
+        match type_path.segments.last().map(|segment| &segment.arguments) {
+            Some(syn::PathArguments::AngleBracketed(arguments)) => arguments
+                .args
+                .iter()
+                .filter_map(|arg| match arg {
+                    syn::GenericArgument::Type(t) => Some(t.clone()),
+                    _ => Option::None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn get_single_type_argument<'t>(&self, type_arguments: &'t [syn::Type], data_type: &syn::Type, path: &str) -> Result<&'t syn::Type, Error> {
+        match type_arguments {
+            [element] => Ok(element),
+            _ => Err(self.unsupported(data_type, path, "expected exactly one type argument")),
+        }
+    }
+
+    fn resolve_type_parameter(&self, t: &syn::Type, type_parameters: &HashMap<String, syn::Type>) -> syn::Type {
+        if let syn::Type::Path(type_path) = t {
+            if let Some(resolved_type) = type_path.path.get_ident().and_then(|ident| type_parameters.get(&ident.to_string())) {
+                return resolved_type.clone();
+            }
+        }
+        t.clone()
+    }
+
+    fn mismatch(&self, value: &Value, data_type: &syn::Type, path: &str) -> Error {
+        Error::msg(format!("{}: Value '{}' does not fit type '{}'.", path, value, quote!(#data_type)))
+    }
+
+    fn unsupported(&self, data_type: &syn::Type, path: &str, reason: &str) -> Error {
+        Error::new(UnsupportedType(format!("{}: Type '{}' cannot be emitted as a literal, {}.", path, quote!(#data_type), reason)))
+    }
+}
+
+fn integer_fits(n: i128, type_name: &str) -> bool {
+    let (min, max): (i128, i128) = match type_name {
+        "i8" => (i8::MIN.into(), i8::MAX.into()),
+        "i16" => (i16::MIN.into(), i16::MAX.into()),
+        "i32" => (i32::MIN.into(), i32::MAX.into()),
+        "i64" | "isize" => (i64::MIN.into(), i64::MAX.into()),
+        "u8" => (0, u8::MAX.into()),
+        "u16" => (0, u16::MAX.into()),
+        "u32" => (0, u32::MAX.into()),
+        "u64" | "usize" => (0, u64::MAX.into()),
+        "u128" => (0, i128::MAX),
+        _ => (i128::MIN, i128::MAX),
+    };
+    n >= min && n <= max
+}
+
+/// Whether `n` is within the finite range of the float type, values too close to zero are rounded.
+fn float_fits(n: f64, type_name: &str) -> bool {
+    match type_name {
+        "f32" => n.abs() <= f64::from(f32::MAX),
+        _ => n.is_finite(),
+    }
+}
+
+#[test]
+fn test_emit_literal() {
+    use crate::package::compact_code;
+
+    let package_json = r#"
+    {
+        "name":"my_package",
+        "version":"1.0.0",
+        "no_std":true,
+        "crates":{
+           "my_crate":{
+              "types":{
+                 "Config":{
+                    "type_parameters":["T"],
+                    "fields":[
+                       {"name":"count","type":"u8"},
+                       {"name":"scale","type":"f32"},
+                       {"name":"label","type":"&'static str"},
+                       {"name":"limit","type":"Option<T>"},
+                       {"name":"pairs","type":"[(i16, bool); 2]"}
+                    ],
+                    "constructors":{}
+                 },
+                 "Opaque":{"constructors":{}}
+              },
+              "modules":{}
+           }
+        }
+    }
+    "#;
+
+    let mut pm = PackageManager::new();
+    pm.add_package(serde_json::from_str(package_json).expect("wrong format."));
+
+    let no_alloc = DataLiteralEmitter { pack_man: &pm, allow_alloc: false };
+    let data_type: syn::Type = syn::parse_str("my_crate::Config<u32>").unwrap();
+    let value = serde_json::json!({"count": 3, "scale": 2, "label": "a \"b\"", "pairs": [[-1, true], [2, false]]});
+    let code = no_alloc.emit_literal(&value, &data_type, "$.node").expect("literal not emitted.").to_string();
+    assert_eq!(
+        compact_code(&code),
+        compact_code(r#"my_crate::Config { count: 3u8, scale: 2.0f32, label: "a \"b\"", limit: None, pairs: [(-1i16, true,), (2i16, false,)] }"#)
+    );
+
+    let value = serde_json::json!({"count": 3, "scale": 2, "label": "", "limit": 7, "pairs": [[0, true], [0, true]]});
+    let code = no_alloc.emit_literal(&value, &data_type, "$.node").expect("literal not emitted.").to_string();
+    assert!(compact_code(&code).contains("Some(7u32)"));
+
+    let value = serde_json::json!({"count": 300, "scale": 2, "label": "", "pairs": [[0, true], [0, true]]});
+    let err = no_alloc.emit_literal(&value, &data_type, "$.node").unwrap_err();
+    assert!(err.to_string().starts_with("$.node.count:"));
+    assert!(!err.is::<UnsupportedType>());
+
+    let value = serde_json::json!({"count": 3, "scale": 1e40, "label": "", "pairs": [[0, true], [0, true]]});
+    let err = no_alloc.emit_literal(&value, &data_type, "$.node").unwrap_err().to_string();
+    assert!(err.starts_with("$.node.scale:"));
+    let f64_type: syn::Type = syn::parse_str("f64").unwrap();
+    assert!(no_alloc.emit_literal(&serde_json::json!(1e40), &f64_type, "$").is_ok());
+
+    let vec_type: syn::Type = syn::parse_str("Vec<String>").unwrap();
+    let value = serde_json::json!(["x", "y"]);
+    assert!(no_alloc.emit_literal(&value, &vec_type, "$").unwrap_err().is::<UnsupportedType>());
+    let with_alloc = DataLiteralEmitter { pack_man: &pm, allow_alloc: true };
+    let code = with_alloc.emit_literal(&value, &vec_type, "$").expect("literal not emitted.").to_string();
+    assert_eq!(compact_code(&code), compact_code(r#"vec![String::from("x"), String::from("y")]"#));
+
+    let opaque_type: syn::Type = syn::parse_str("my_crate::Opaque").unwrap();
+    assert!(with_alloc.emit_literal(&serde_json::json!({}), &opaque_type, "$").unwrap_err().is::<UnsupportedType>());
+}
@@ -0,0 +1,186 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::{Error, Result};
+
+use crate::flow_model::{CodeEmitter, NoStdCodeEmitter, StandardCodeEmitter, StandardEmitterOptions};
+
+/// Name of the emitter used if a project does not select one.
+pub const STANDARD_EMITTER_NAME: &str = "standard";
+
+pub const NO_STD_EMITTER_NAME: &str = "no_std";
+
+/// Creates an emitter from the options of a project.
+pub type CodeEmitterFactory = Arc<dyn Fn(&Value) -> Result<Box<dyn CodeEmitter>, Error> + Send + Sync>;
+
+/// Emitter selection of a project.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct EmitterSelection {
+    pub name: String,
+    /// Emitter specific options, passed unchanged to the emitter factory.
+    #[serde(default)]
+    pub options: Value,
+}
+
+impl Default for EmitterSelection {
+    fn default() -> Self {
+        Self {
+            name: STANDARD_EMITTER_NAME.to_string(),
+            options: Value::Null,
+        }
+    }
+}
+
+/// Name and description of a registered emitter.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmitterInfo {
+    pub name: String,
+    pub description: String,
+}
+
+struct RegisteredEmitter {
+    description: String,
+    factory: CodeEmitterFactory,
+}
+
+/// Code emitters by name. Applications embedding the project manager can register their own emitters,
+/// e.g. for embedded targets.
+#[derive(Clone)]
+pub struct EmitterRegistry {
+    emitters: HashMap<String, Arc<RegisteredEmitter>>,
+}
+
+impl EmitterRegistry {
+    /// Creates a registry with the built-in emitters.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            emitters: HashMap::new(),
+        };
+        registry.register(
+            STANDARD_EMITTER_NAME,
+            "Generates a flowrs flow for the browser (wasm-bindgen) and the desktop runner (FFI).",
+            Arc::new(|options| {
+                let options: StandardEmitterOptions = if options.is_null() {
+                    StandardEmitterOptions::default()
+                } else {
+                    serde_json::from_value(options.clone())?
+                };
+                Ok(Box::new(StandardCodeEmitter { options }) as Box<dyn CodeEmitter>)
+            }),
+        );
+        registry.register(
+            NO_STD_EMITTER_NAME,
+            "Generates #![no_std] code for embedded targets. Nodes are returned by value and flow data is converted to Rust literals at build time. Requires packages marked no_std.",
+            Arc::new(|_| Ok(Box::new(NoStdCodeEmitter {}) as Box<dyn CodeEmitter>)),
+        );
+        registry
+    }
+
+    /// Registers an emitter. An emitter with the same name is replaced.
+    pub fn register(&mut self, name: &str, description: &str, factory: CodeEmitterFactory) {
+        self.emitters.insert(
+            name.to_string(),
+            Arc::new(RegisteredEmitter {
+                description: description.to_string(),
+                factory,
+            }),
+        );
+    }
+
+    /// Lists all registered emitters sorted by name.
+    pub fn get_emitter_infos(&self) -> Vec<EmitterInfo> {
+        let mut infos: Vec<EmitterInfo> = self
+            .emitters
+            .iter()
+            .map(|(name, emitter)| EmitterInfo {
+                name: name.clone(),
+                description: emitter.description.clone(),
+            })
+            .collect();
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        infos
+    }
+
+    /// Creates the selected emitter. Fails if the emitter is unknown or rejects its options.
+    pub fn create_emitter(&self, selection: &EmitterSelection) -> Result<Box<dyn CodeEmitter>, Error> {
+        let emitter = self.emitters.get(&selection.name).ok_or_else(|| {
+            Error::msg(format!(
+                "Unknown emitter '{}'. Available emitters: {}",
+                selection.name,
+                self.get_emitter_infos().iter().map(|i| i.name.clone()).collect::<Vec<String>>().join(", ")
+            ))
+        })?;
+
+        (emitter.factory)(&selection.options)
+            .map_err(|err| Error::msg(format!("Invalid options for emitter '{}': {}", selection.name, err)))
+    }
+}
+
+impl Default for EmitterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for EmitterRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&String> = self.emitters.keys().collect();
+        names.sort();
+        f.debug_struct("EmitterRegistry").field("emitters", &names).finish()
+    }
+}
+
+#[test]
+fn test_emitter_registry() {
+    struct CommentEmitter {
+        comment: String,
+    }
+
+    impl CodeEmitter for CommentEmitter {
+        fn emit_flow_code(&self, _flow: &crate::flow_model::FlowModel, _pm: &crate::package_manager::PackageManager) -> Result<String, Error> {
+            Ok(format!("// {}", self.comment))
+        }
+    }
+
+    let mut registry = EmitterRegistry::new();
+    registry.register(
+        "comment",
+        "Emits a single comment.",
+        Arc::new(|options| {
+            let comment = options
+                .get("comment")
+                .and_then(|c| c.as_str())
+                .ok_or_else(|| Error::msg("Option 'comment' is missing."))?;
+            Ok(Box::new(CommentEmitter { comment: comment.to_string() }) as Box<dyn CodeEmitter>)
+        }),
+    );
+
+    let names: Vec<String> = registry.get_emitter_infos().into_iter().map(|i| i.name).collect();
+    assert_eq!(names, vec!["comment".to_string(), NO_STD_EMITTER_NAME.to_string(), STANDARD_EMITTER_NAME.to_string()]);
+
+    let flow: crate::flow_model::FlowModel = serde_json::from_str(r#"{"nodes": {}, "connections": [], "data": {}}"#).expect("wrong format.");
+    let pm = crate::package_manager::PackageManager::new();
+
+    let selection: EmitterSelection = serde_json::from_str(r#"{"name": "comment", "options": {"comment": "hello"}}"#).expect("wrong format.");
+    let emitter = registry.create_emitter(&selection).expect("emitter not created.");
+    assert_eq!(emitter.emit_flow_code(&flow, &pm).expect("code generation failed."), "// hello");
+
+    let selection: EmitterSelection = serde_json::from_str(r#"{"name": "comment"}"#).expect("wrong format.");
+    assert!(registry.create_emitter(&selection).is_err());
+
+    let selection: EmitterSelection = serde_json::from_str(r#"{"name": "unknown"}"#).expect("wrong format.");
+    assert!(registry.create_emitter(&selection).is_err());
+
+    assert!(registry.create_emitter(&EmitterSelection::default()).is_ok());
+
+    let selection: EmitterSelection = serde_json::from_str(r#"{"name": "standard", "options": {"embed_data": true}}"#).expect("wrong format.");
+    assert!(registry.create_emitter(&selection).is_ok());
+
+    let selection: EmitterSelection = serde_json::from_str(r#"{"name": "standard", "options": {"embed": true}}"#).expect("wrong format.");
+    assert!(registry.create_emitter(&selection).is_err());
+}
@@ -0,0 +1,442 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::flow_model::{ConnectionModel, FlowModel};
+
+use anyhow::Result;
+
+/// Semantic difference between two flows.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FlowDiff {
+    pub added_nodes: Vec<String>,
+    pub removed_nodes: Vec<String>,
+    pub changed_nodes: Vec<NodeChange>,
+    pub added_connections: Vec<ConnectionModel>,
+    pub removed_connections: Vec<ConnectionModel>,
+    /// Paths into the flow data (e.g. "node.value") whose value changed, was added or was removed.
+    /// Objects are compared key by key, all other values (including arrays) as a whole.
+    pub changed_data: Vec<String>,
+    pub layout_changed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NodeChange {
+    pub name: String,
+    pub old_type: String,
+    pub new_type: String,
+    /// Names of the node fields that differ (e.g. "node_type", "type_parameters").
+    pub changed_fields: Vec<String>,
+}
+
+impl NodeChange {
+    pub fn is_retyped(&self) -> bool {
+        self.old_type != self.new_type
+    }
+}
+
+impl FlowDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.changed_nodes.is_empty()
+            && self.added_connections.is_empty()
+            && self.removed_connections.is_empty()
+            && self.changed_data.is_empty()
+            && !self.layout_changed
+    }
+}
+
+impl fmt::Display for FlowDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in &self.added_nodes {
+            writeln!(f, "+ node {}", name)?;
+        }
+        for name in &self.removed_nodes {
+            writeln!(f, "- node {}", name)?;
+        }
+        for change in &self.changed_nodes {
+            if change.is_retyped() {
+                writeln!(f, "~ node {} ({} -> {}): {}", change.name, change.old_type, change.new_type, change.changed_fields.join(", "))?;
+            } else {
+                writeln!(f, "~ node {}: {}", change.name, change.changed_fields.join(", "))?;
+            }
+        }
+        for connection in &self.added_connections {
+            writeln!(f, "+ connection {}", format_connection(connection))?;
+        }
+        for connection in &self.removed_connections {
+            writeln!(f, "- connection {}", format_connection(connection))?;
+        }
+        for key in &self.changed_data {
+            writeln!(f, "~ data {}", key)?;
+        }
+        if self.layout_changed {
+            writeln!(f, "~ layout")?;
+        }
+        Ok(())
+    }
+}
+
+fn format_connection(connection: &ConnectionModel) -> String {
+    format!(
+        "{}.{} -> {}.{}",
+        connection.from_node, connection.from_output, connection.to_node, connection.to_input
+    )
+}
+
+/// Computes the semantic difference between `old` and `new`.
+pub fn diff_flows(old: &FlowModel, new: &FlowModel) -> FlowDiff {
+    let mut diff = FlowDiff::default();
+
+    let node_names: BTreeSet<&String> = old.nodes.keys().chain(new.nodes.keys()).collect();
+    for name in node_names {
+        match (old.nodes.get(name), new.nodes.get(name)) {
+            (None, Some(_)) => diff.added_nodes.push(name.clone()),
+            (Some(_), None) => diff.removed_nodes.push(name.clone()),
+            (Some(old_node), Some(new_node)) => {
+                let changed_fields = changed_keys(&to_object(old_node), &to_object(new_node));
+                if !changed_fields.is_empty() {
+                    diff.changed_nodes.push(NodeChange {
+                        name: name.clone(),
+                        old_type: old_node.node_type.clone(),
+                        new_type: new_node.node_type.clone(),
+                        changed_fields,
+                    });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    diff.added_connections = new
+        .connections
+        .iter()
+        .filter(|c| !old.connections.contains(c))
+        .cloned()
+        .collect();
+    diff.removed_connections = old
+        .connections
+        .iter()
+        .filter(|c| !new.connections.contains(c))
+        .cloned()
+        .collect();
+
+    changed_data_paths("", &data_object(&old.data), &data_object(&new.data), &mut diff.changed_data);
+    diff.layout_changed = to_object(&old.layout) != to_object(&new.layout);
+
+    diff
+}
+
+fn to_object<T: Serialize>(value: &T) -> Map<String, Value> {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+fn data_object(data: &Value) -> Map<String, Value> {
+    data.as_object().cloned().unwrap_or_default()
+}
+
+fn changed_keys(old: &Map<String, Value>, new: &Map<String, Value>) -> Vec<String> {
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    keys.into_iter()
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect()
+}
+
+fn changed_data_paths(path: &str, old: &Map<String, Value>, new: &Map<String, Value>, paths: &mut Vec<String>) {
+    for key in changed_keys(old, new) {
+        let key_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+        match (old.get(&key), new.get(&key)) {
+            (Some(Value::Object(old)), Some(Value::Object(new))) => changed_data_paths(&key_path, old, new, paths),
+            _ => paths.push(key_path),
+        }
+    }
+}
+
+/// An element that was changed differently on both sides of a merge.
+/// The merged flow contains the `ours` version of it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MergeConflict {
+    pub path: String,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format_side = |v: &Option<Value>| v.as_ref().map_or("<none>".to_string(), |v| v.to_string());
+        write!(
+            f,
+            "{}: base {}, ours {}, theirs {}",
+            self.path,
+            format_side(&self.base),
+            format_side(&self.ours),
+            format_side(&self.theirs)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    pub flow: FlowModel,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Three-way merge of `ours` and `theirs` with their common ancestor `base`.
+/// Nodes, data and layout are merged field by field, connections as sets.
+/// Connections to nodes that are not in the merged flow (e.g. added on one side to a node removed
+/// on the other) are dropped and reported as conflicts.
+pub fn merge_flows(base: &FlowModel, ours: &FlowModel, theirs: &FlowModel) -> Result<MergeResult> {
+    let mut conflicts = Vec::new();
+
+    let nodes = merge_maps(
+        "$.nodes",
+        &to_object(&base.nodes),
+        &to_object(&ours.nodes),
+        &to_object(&theirs.nodes),
+        true,
+        &mut conflicts,
+    );
+    let data = merge_maps(
+        "$.data",
+        &data_object(&base.data),
+        &data_object(&ours.data),
+        &data_object(&theirs.data),
+        true,
+        &mut conflicts,
+    );
+    let layout = merge_maps(
+        "$.layout",
+        &to_object(&base.layout),
+        &to_object(&ours.layout),
+        &to_object(&theirs.layout),
+        true,
+        &mut conflicts,
+    );
+
+    // A connection survives if it is in base and was removed on neither side, or if either side added it.
+    let mut connections: Vec<ConnectionModel> = ours
+        .connections
+        .iter()
+        .filter(|c| !base.connections.contains(c) || theirs.connections.contains(c))
+        .cloned()
+        .collect();
+    for connection in &theirs.connections {
+        if !base.connections.contains(connection) && !connections.contains(connection) {
+            connections.push(connection.clone());
+        }
+    }
+
+    connections.retain(|connection| {
+        let is_connected = nodes.contains_key(&connection.from_node) && nodes.contains_key(&connection.to_node);
+        if !is_connected {
+            let value = serde_json::to_value(connection).ok();
+            let (ours_value, theirs_value) = if ours.connections.contains(connection) {
+                (value, Option::None)
+            } else {
+                (Option::None, value)
+            };
+            conflicts.push(MergeConflict {
+                path: format!("$.connections[{}]", format_connection(connection)),
+                base: Option::None,
+                ours: ours_value,
+                theirs: theirs_value,
+            });
+        }
+        is_connected
+    });
+
+    let flow: FlowModel = serde_json::from_value(serde_json::json!({
+        "nodes": nodes,
+        "connections": connections,
+        "data": data,
+        "layout": layout,
+    }))?;
+
+    Ok(MergeResult { flow, conflicts })
+}
+
+fn merge_maps(
+    path: &str,
+    base: &Map<String, Value>,
+    ours: &Map<String, Value>,
+    theirs: &Map<String, Value>,
+    nested: bool,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Map<String, Value> {
+    let mut merged = Map::new();
+
+    let keys: BTreeSet<&String> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+    for key in keys {
+        let entry_path = format!("{}.{}", path, key);
+        let (b, o, t) = (base.get(key), ours.get(key), theirs.get(key));
+
+        let value = match (b, o, t) {
+            // Both sides changed an existing object: merge its fields.
+            (Some(Value::Object(b)), Some(Value::Object(o)), Some(Value::Object(t))) if nested && o != t => {
+                Some(Value::Object(merge_maps(&entry_path, b, o, t, false, conflicts)))
+            }
+            _ => {
+                if o == t || t == b {
+                    o.cloned()
+                } else if o == b {
+                    t.cloned()
+                } else {
+                    conflicts.push(MergeConflict {
+                        path: entry_path,
+                        base: b.cloned(),
+                        ours: o.cloned(),
+                        theirs: t.cloned(),
+                    });
+                    o.cloned()
+                }
+            }
+        };
+
+        if let Some(value) = value {
+            merged.insert(key.clone(), value);
+        }
+    }
+
+    merged
+}
+
+#[test]
+fn test_diff_flows() {
+    let old: FlowModel = serde_json::from_str(
+        r#"
+    {
+        "nodes": {
+            "a": {"node_type": "x::A", "type_parameters": {}, "constructor": "New"},
+            "b": {"node_type": "x::B", "type_parameters": {}, "constructor": "New"},
+            "c": {"node_type": "x::C", "type_parameters": {}, "constructor": "New"}
+        },
+        "connections": [
+            {"from_node": "a", "from_output": "output", "to_node": "b", "to_input": "input"}
+        ],
+        "data": {"a": {"value": 1}}
+    }
+    "#,
+    )
+    .expect("wrong format.");
+
+    let new: FlowModel = serde_json::from_str(
+        r#"
+    {
+        "nodes": {
+            "a": {"node_type": "x::A", "type_parameters": {}, "constructor": "New"},
+            "b": {"node_type": "x::B2", "type_parameters": {}, "constructor": "New"},
+            "d": {"node_type": "x::D", "type_parameters": {}, "constructor": "New"}
+        },
+        "connections": [
+            {"from_node": "a", "from_output": "output", "to_node": "d", "to_input": "input"}
+        ],
+        "data": {"a": {"value": 2}}
+    }
+    "#,
+    )
+    .expect("wrong format.");
+
+    let diff = diff_flows(&old, &new);
+    assert_eq!(diff.added_nodes, vec!["d".to_string()]);
+    assert_eq!(diff.removed_nodes, vec!["c".to_string()]);
+    assert_eq!(diff.changed_nodes.len(), 1);
+    assert!(diff.changed_nodes[0].is_retyped());
+    assert_eq!(diff.changed_nodes[0].changed_fields, vec!["node_type".to_string()]);
+    assert_eq!(diff.added_connections[0].to_node, "d");
+    assert_eq!(diff.removed_connections[0].to_node, "b");
+    assert_eq!(diff.changed_data, vec!["a.value".to_string()]);
+    assert!(!diff.layout_changed);
+    assert!(diff_flows(&old, &old).is_empty());
+
+    let mut nested = new.clone();
+    nested.data = serde_json::json!({"a": {"value": 2, "config": {"rate": 5}}, "b": [1, 2]});
+    assert_eq!(diff_flows(&new, &nested).changed_data, vec!["a.config".to_string(), "b".to_string()]);
+    let mut changed_nested = nested.clone();
+    changed_nested.data["a"]["config"]["rate"] = serde_json::json!(6);
+    changed_nested.data["b"] = serde_json::json!([1]);
+    assert_eq!(diff_flows(&nested, &changed_nested).changed_data, vec!["a.config.rate".to_string(), "b".to_string()]);
+}
+
+#[test]
+fn test_merge_flows() {
+    let base: FlowModel = serde_json::from_str(
+        r#"
+    {
+        "nodes": {
+            "a": {"node_type": "x::A", "type_parameters": {"I": "i32"}, "constructor": "New"},
+            "b": {"node_type": "x::B", "type_parameters": {}, "constructor": "New"}
+        },
+        "connections": [
+            {"from_node": "a", "from_output": "output", "to_node": "b", "to_input": "input"}
+        ],
+        "data": {"a": {"value": 1}}
+    }
+    "#,
+    )
+    .expect("wrong format.");
+
+    // Ours: relabels a, changes data, adds c connected to a.
+    let ours: FlowModel = serde_json::from_str(
+        r#"
+    {
+        "nodes": {
+            "a": {"node_type": "x::A", "type_parameters": {"I": "i32"}, "constructor": "New", "label": "A"},
+            "b": {"node_type": "x::B", "type_parameters": {}, "constructor": "New"},
+            "c": {"node_type": "x::C", "type_parameters": {}, "constructor": "New"}
+        },
+        "connections": [
+            {"from_node": "a", "from_output": "output", "to_node": "b", "to_input": "input"},
+            {"from_node": "a", "from_output": "output", "to_node": "c", "to_input": "input"}
+        ],
+        "data": {"a": {"value": 2}}
+    }
+    "#,
+    )
+    .expect("wrong format.");
+
+    // Theirs: retypes a's parameter, changes data differently, removes b and its connection.
+    let theirs: FlowModel = serde_json::from_str(
+        r#"
+    {
+        "nodes": {
+            "a": {"node_type": "x::A", "type_parameters": {"I": "i64"}, "constructor": "New"}
+        },
+        "connections": [],
+        "data": {"a": {"value": 3}}
+    }
+    "#,
+    )
+    .expect("wrong format.");
+
+    let result = merge_flows(&base, &ours, &theirs).expect("merge failed.");
+
+    let mut node_names: Vec<&String> = result.flow.nodes.keys().collect();
+    node_names.sort();
+    assert_eq!(node_names, vec!["a", "c"]);
+    let node_a = serde_json::to_value(&result.flow.nodes["a"]).unwrap();
+    assert_eq!(node_a["type_parameters"]["I"], "i64");
+    assert_eq!(node_a["label"], "A");
+    assert_eq!(result.flow.connections.len(), 1);
+    assert_eq!(result.flow.connections[0].to_node, "c");
+
+    assert_eq!(result.conflicts.len(), 1);
+    assert_eq!(result.conflicts[0].path, "$.data.a.value");
+    assert_eq!(result.flow.data["a"]["value"], 2);
+
+    // Ours connects a new node to b, which theirs removed: the connection is dropped.
+    let mut ours_to_removed = ours.clone();
+    ours_to_removed.connections.push(serde_json::from_str(r#"{"from_node": "c", "from_output": "output", "to_node": "b", "to_input": "input"}"#).unwrap());
+    let result = merge_flows(&base, &ours_to_removed, &theirs).expect("merge failed.");
+    assert_eq!(result.flow.connections.len(), 1);
+    assert_eq!(result.flow.connections[0].to_node, "c");
+    let conflict = result.conflicts.iter().find(|c| c.path.starts_with("$.connections")).expect("conflict missing.");
+    assert_eq!(conflict.path, "$.connections[c.output -> b.input]");
+    assert!(conflict.ours.is_some() && conflict.theirs.is_none());
+}
@@ -1,210 +1,885 @@
 use proc_macro2::TokenStream;
 use quote::quote;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use syn::Ident;
 
-use std::path::PathBuf;
-use std::process::Command;
-
-use crate::package::{Constructor, Namespace, ObjectDescription, Package};
+use crate::package::{self, ArgumentConstruction, Constructor, JsonDataSource, Namespace, ObjectDescription, ValidationIssue};
 use crate::package_manager::PackageManager;
 
 use anyhow::{Error, Result};
+use jsonschema::JSONSchema;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct ConnectionModel {
-    from_node: String,
-    to_node: String,
-    to_input: String,
-    from_output: String,
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct ConnectionModel {
+    pub(crate) from_node: String,
+    pub(crate) to_node: String,
+    pub(crate) to_input: String,
+    pub(crate) from_output: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct NodeModel {
-    node_type: String,
+    pub(crate) node_type: String,
     type_parameters: HashMap<String, String>,
-    constructor: String
+    constructor: String,
+    /// Per-node replacement of the construction of constructor arguments, by argument name.
+    #[serde(default)]
+    arguments: HashMap<String, ArgumentConstruction>,
+    /// Description of the node. Falls back to the documentation of the node type.
+    description: Option<String>,
+    /// Display name of the node. Falls back to the node name.
+    label: Option<String>,
+    /// Tags for editors and tools. Not used for code generation.
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Position in the editor. Not used for code generation.
+    position: Option<NodePosition>,
     //inputs: HashMap<String, InputModel>,
     //outputs: HashMap<String, OutputModel>
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct NodePosition {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Editor metadata of a flow. Not used for code generation.
+/// Node positions are stored in the nodes themselves (see NodeModel).
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct FlowLayout {
+    /// Per-node appearance, by node name.
+    #[serde(default)]
+    pub nodes: HashMap<String, NodeLayout>,
+    #[serde(default)]
+    pub notes: Vec<StickyNote>,
+    #[serde(default)]
+    pub groups: Vec<NodeGroup>,
+    pub viewport: Option<Viewport>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct NodeLayout {
+    pub color: Option<String>,
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub collapsed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct StickyNote {
+    pub id: String,
+    pub text: String,
+    pub position: NodePosition,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct NodeGroup {
+    pub id: String,
+    pub label: Option<String>,
+    /// Names of the grouped nodes.
+    pub nodes: Vec<String>,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct Viewport {
+    pub x: f64,
+    pub y: f64,
+    pub zoom: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct FlowModel {
-    nodes: HashMap<String, NodeModel>,
-    connections: Vec<ConnectionModel>,
-    data: Value,
+    pub(crate) nodes: HashMap<String, NodeModel>,
+    pub(crate) connections: Vec<ConnectionModel>,
+    pub(crate) data: Value,
+    #[serde(default)]
+    pub(crate) layout: FlowLayout,
+    /// Flow inputs by name. Values pushed into an input are sent to the bound node input.
+    #[serde(default)]
+    pub(crate) inputs: HashMap<String, FlowPort>,
+    /// Flow outputs by name. Values sent by the bound node output are passed to the subscribers.
+    #[serde(default)]
+    pub(crate) outputs: HashMap<String, FlowPort>,
+}
+
+/// Binding of a flow input or output to a port of a node.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct FlowPort {
+    pub node: String,
+    pub port: String,
+    /// Rust type of the values passing the port.
+    #[serde(rename = "type")]
+    pub data_type: String,
+}
+
+impl NodeModel {
+    fn to_object_description(&self, node_name: &str, pm: &PackageManager) -> Result<ObjectDescription, Error> {
+        let type_arguments = match pm.get_type(&self.node_type).and_then(|t| t.type_parameters.as_ref()) {
+            Some(type_parameters) => Self::emit_type_arguments(type_parameters, &self.type_parameters, pm)?,
+            None => Vec::new(),
+        };
+        let node_type_path = package::emit_type_path(&self.node_type)
+            .map_err(|err| anyhow::Error::msg(format!("Node '{}': {}", node_name, err)))?;
+
+        Ok(ObjectDescription {
+            name: node_name.to_string(),
+            type_name: self.node_type.clone(),
+            object_type: package::emit_generic_type(node_type_path, &type_arguments),
+            is_mutable: false,
+            argument_overrides: self.arguments.clone(),
+        })
+    }
+
+    /// Types the type parameters are resolved to. Resolved types with type parameters of their own get them resolved from the same map.
+    fn emit_type_arguments(type_parameters: &[String], resolved_type_parameters: &HashMap<String, String>, pm: &PackageManager) -> Result<Vec<syn::Type>, Error> {
+        type_parameters
+            .iter()
+            .map(|type_parameter| {
+                let type_name = resolved_type_parameters
+                    .get(type_parameter)
+                    .ok_or_else(|| anyhow::Error::msg(format!("Type parameter '{}' is not set.", type_parameter)))?;
+
+                match pm.get_type(type_name).and_then(|t| t.type_parameters.as_ref()) {
+                    Some(tps) if !tps.is_empty() => Ok(package::emit_generic_type(
+                        package::emit_type_path(type_name)?,
+                        &Self::emit_type_arguments(tps, resolved_type_parameters, pm)?,
+                    )),
+                    _ => syn::parse_str::<syn::Type>(type_name)
+                        .map_err(|err| anyhow::Error::msg(format!("Invalid type '{}': {}", type_name, err))),
+                }
+            })
+            .collect()
+    }
+}
+
+impl FlowModel {
+    pub fn layout(&self) -> &FlowLayout {
+        &self.layout
+    }
+
+    pub fn set_layout(&mut self, layout: FlowLayout) {
+        self.layout = layout;
+    }
+
+    /// Checks the data of all objects built from Json against the data schemas of their types.
+    /// Types without data schema are not checked.
+    pub fn validate_data(&self, pm: &PackageManager) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        let mut node_names: Vec<&String> = self.nodes.keys().collect();
+        node_names.sort();
+
+        for node_name in node_names {
+            let node = &self.nodes[node_name];
+            let node_path = format!("$.nodes.{}", node_name);
+
+            let constructor = match pm.get_type(&node.node_type).and_then(|t| t.constructors.get(&node.constructor)) {
+                Some(c) => c,
+                None => {
+                    issues.push(ValidationIssue::new(
+                        node_path,
+                        format!("Cannot find constructor '{}' for type '{}'.", node.constructor, node.node_type),
+                    ));
+                    continue;
+                }
+            };
+
+            let obj_desc = match node.to_object_description(node_name, pm) {
+                Ok(obj_desc) => obj_desc,
+                Err(err) => {
+                    issues.push(ValidationIssue::new(node_path, err.to_string()));
+                    continue;
+                }
+            };
+
+            let mut bindings = Vec::new();
+            if let Err(err) = constructor.collect_json_data_bindings(&obj_desc, &node.type_parameters, pm, &Namespace::new(), &mut bindings) {
+                issues.push(ValidationIssue::new(node_path, err.to_string()));
+                continue;
+            }
+
+            for binding in bindings {
+                let schema = match pm.get_type(&binding.type_name).and_then(|t| t.data_schema.as_ref()) {
+                    Some(schema) => schema,
+                    None => continue,
+                };
+
+                // Missing entries are Null, just like in the generated code.
+                let value = binding.path.iter().fold(&self.data, |value, part| &value[part.as_str()]);
+                let data_path = format!("$.data.{}", binding.path.join("."));
+
+                match JSONSchema::compile(schema) {
+                    Ok(compiled_schema) => {
+                        if let Err(errors) = compiled_schema.validate(value) {
+                            for error in errors {
+                                let pointer = error.instance_path.to_string();
+                                issues.push(ValidationIssue::new(
+                                    data_path.clone(),
+                                    if pointer.is_empty() { format!("{}", error) } else { format!("{} (at {})", error, pointer) },
+                                ));
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        issues.push(ValidationIssue::new(
+                            node_path.clone(),
+                            format!("Invalid data schema for type '{}': {}", binding.type_name, err),
+                        ));
+                    }
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Checks the flow inputs and outputs: names must be Rust identifiers, nodes must exist, ports must be
+    /// declared by the node type (if it declares its ports) and types must parse.
+    pub fn validate_ports(&self, pm: &PackageManager) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        for (kind, ports) in [("inputs", &self.inputs), ("outputs", &self.outputs)] {
+            let mut names: Vec<&String> = ports.keys().collect();
+            names.sort();
+
+            for name in names {
+                let port = &ports[name];
+                let port_path = format!("$.{}.{}", kind, name);
+
+                if syn::parse_str::<Ident>(name).is_err() {
+                    issues.push(ValidationIssue::new(port_path.clone(), format!("'{}' is not a valid name.", name)));
+                }
+                if kind == "outputs" && self.inputs.contains_key(name) {
+                    issues.push(ValidationIssue::new(port_path.clone(), format!("'{}' is both an input and an output.", name)));
+                }
+                if syn::parse_str::<syn::Type>(&port.data_type).is_err() {
+                    issues.push(ValidationIssue::new(format!("{}.type", port_path), format!("Cannot parse type '{}'.", port.data_type)));
+                }
+
+                let node = match self.nodes.get(&port.node) {
+                    Some(node) => node,
+                    None => {
+                        issues.push(ValidationIssue::new(format!("{}.node", port_path), format!("Cannot find node '{}'.", port.node)));
+                        continue;
+                    }
+                };
+
+                let node_ports = pm
+                    .get_type(&node.node_type)
+                    .and_then(|t| if kind == "inputs" { t.inputs.as_ref() } else { t.outputs.as_ref() });
+                if let Some(node_ports) = node_ports {
+                    if !node_ports.contains(&port.port) {
+                        issues.push(ValidationIssue::new(
+                            format!("{}.port", port_path),
+                            format!("Type '{}' has no {} '{}'.", node.node_type, &kind[..kind.len() - 1], port.port),
+                        ));
+                    }
+                }
+            }
+        }
+
+        issues
+    }
 }
 
 pub trait CodeEmitter {
     fn emit_flow_code(&self, flow: &FlowModel, pm: &PackageManager) -> Result<String, Error>;
+
+    /// Whether the emitted code is a `#![no_std]` library. Its crate gets neither the wasm, native and ffi
+    /// features nor their dependencies, and no index.html.
+    fn is_no_std(&self) -> bool {
+        false
+    }
+
+    /// Crates the emitted code uses besides flowrs and the flow's packages, e.g. `serde_json`.
+    /// Generated projects only depend on the `builtin_dependencies` listed here.
+    fn get_dependencies(&self, _flow: &FlowModel, _pm: &PackageManager) -> Result<Vec<String>, Error> {
+        Ok(Vec::new())
+    }
 }
 
-pub struct StandardCodeEmitter {}
+/// Options of the standard emitter, set with the `options` of a project's emitter selection.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct StandardEmitterOptions {
+    /// Emit flow data as typed Rust expressions where the types allow it (see DataLiteralEmitter)
+    /// instead of parsing all data from JSON at startup. Other data is still parsed from JSON.
+    #[serde(default)]
+    pub embed_data: bool,
+}
+
+#[derive(Default)]
+pub struct StandardCodeEmitter {
+    pub options: StandardEmitterOptions,
+}
 
 impl StandardCodeEmitter {
-    fn emit_functions(&self, init_function_body: &TokenStream) -> TokenStream {
+    fn emit_functions(
+        &self,
+        node_idents: &Vec<Ident>,
+        node_types: &Vec<syn::Type>,
+        create_nodes_body: &TokenStream,
+        init_function_body: &TokenStream,
+        flow_ports: &TokenStream,
+        flow_handle: &TokenStream,
+    ) -> TokenStream {
         quote! {
 
-            #[cfg(target_arch = "wasm32")]
+            #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
             #[wasm_bindgen]
             extern "C" {
                 # [wasm_bindgen (js_namespace = console)]
                 fn log(s: &str);
             }
-            #[cfg(target_arch = "wasm32")]
+            #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
             macro_rules ! println { ($ ($ t : tt) *) => { log (format ! ($ ($ t) *) . as_str ()) ; } }
-            #[cfg(target_arch = "wasm32")]
+            #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
             #[wasm_bindgen]
-            pub fn wasm_run() {
+            pub fn wasm_run() -> Result<(), JsValue> {
                 
-                let mut ctx = Box::new(init());
+                let mut ctx = Box::new(init().map_err(|err| JsValue::from_str(&err.to_string()))?);
 
                 let node_updater = SingleThreadedNodeUpdater::new(None);
                 let scheduler = RoundRobinScheduler::new();
 
                 let res = ctx.executor.run(ctx.flow, scheduler, node_updater);
+
+                Ok(())
             }
 
-            #[cfg(not(target_arch = "wasm32"))]
+            #[cfg(feature = "native")]
+            pub fn run_native(mut ctx: ExecutionContext, num_workers: usize) -> String {
+                let node_updater = MultiThreadedNodeUpdater::new(num_workers);
+                let scheduler = RoundRobinScheduler::new();
+
+                let res = ctx.executor.run(ctx.flow, scheduler, node_updater);
+
+                format!("{:?}", res)
+            }
+
+            #[cfg(feature = "ffi")]
+            static LAST_INIT_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+            #[cfg(feature = "ffi")]
             #[no_mangle]
             pub extern "C" fn native_init() -> *mut ExecutionContextHandle {
-                let ctx = Box::new(init());
-                Box::into_raw(ctx).cast()
+                match init_with_ports() {
+                    Ok((ctx, ports)) => {
+                        let ctx_handle: *mut ExecutionContextHandle = Box::into_raw(Box::new(ctx)).cast();
+                        FLOW_PORTS.lock().unwrap().push((ctx_handle as usize, ports));
+                        ctx_handle
+                    }
+                    Err(err) => {
+                        *LAST_INIT_ERROR.lock().unwrap() = Some(err.to_string());
+                        std::ptr::null_mut()
+                    }
+                }
             }
 
-            #[cfg(not(target_arch = "wasm32"))]
+            #[cfg(feature = "ffi")]
             #[no_mangle]
-            pub extern "C" fn native_run(num_workers: usize, ctx_handle: *mut ExecutionContextHandle) -> *const c_char {
-                let mut ctx = unsafe { Box::from_raw(ctx_handle.cast::<ExecutionContext>()) };
-                
-                let node_updater = MultiThreadedNodeUpdater::new(num_workers);
-                let scheduler = RoundRobinScheduler::new();
+            pub extern "C" fn native_last_error() -> *const c_char {
+                match LAST_INIT_ERROR.lock().unwrap().take() {
+                    Some(message) => CString::new(message).unwrap_or_default().into_raw(),
+                    None => std::ptr::null(),
+                }
+            }
 
-                let res = ctx.executor.run(ctx.flow, scheduler, node_updater);
+            #[cfg(feature = "ffi")]
+            #[no_mangle]
+            pub extern "C" fn native_run(num_workers: usize, ctx_handle: *mut ExecutionContextHandle) -> *const c_char {
+                let ctx = unsafe { Box::from_raw(ctx_handle.cast::<ExecutionContext>()) };
+                let result = run_native(*ctx, num_workers);
+                remove_flow_ports(ctx_handle);
 
-                CString::new(format!("{:?}", res)).expect("Cannot convert result to a C-String.").into_raw()
+                CString::new(result).expect("Cannot convert result to a C-String.").into_raw()
             }
+            #[cfg(feature = "ffi")]
             #[no_mangle]
             pub unsafe extern fn native_free_string(ptr: *const c_char) {
                 let _ = CString::from_raw(ptr as *mut _);
             }
 
-            #[cfg(not(target_arch = "wasm32"))]
+            #[cfg(feature = "ffi")]
             #[no_mangle]
             pub extern "C" fn native_cancel(ctx_handle: *mut ExecutionContextHandle) {
+                remove_flow_ports(ctx_handle);
                 let ctx = unsafe { Box::from_raw(ctx_handle.cast::<ExecutionContext>()) };
                 ctx.executor.controller().lock().unwrap().cancel()
             }
 
 
-            pub fn init() -> ExecutionContext {
+            #[derive(Debug)]
+            pub struct InitError {
+                message: String,
+            }
+
+            impl InitError {
+                fn new(message: String) -> Self {
+                    Self { message: message }
+                }
+            }
+
+            impl std::fmt::Display for InitError {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "{}", self.message)
+                }
+            }
+
+            impl std::error::Error for InitError {}
+
+            pub struct FlowNodes {
+                #(pub #node_idents: #node_types,)*
+            }
+
+            /// Creates and connects all nodes. Entries of `data_override` (a JSON object) replace the flow data of the same name.
+            #[allow(unused_variables)]
+            fn create_nodes(change_observer: Option<&ChangeObserver>, context: Arc<Mutex<Context>>, data_override: Option<&str>) -> Result<FlowNodes, InitError> {
+                #create_nodes_body
+            }
+
+            fn init_flow<P>(create_ports: impl FnOnce(&mut FlowNodes) -> P) -> Result<(ExecutionContext, P), InitError> {
                 #init_function_body
             }
+
+            pub fn init() -> Result<ExecutionContext, InitError> {
+                init_flow(|_| ()).map(|(ctx, _)| ctx)
+            }
+
+            /// Like `init`, but also returns the handles of the flow inputs and outputs.
+            pub fn init_with_ports() -> Result<(ExecutionContext, FlowPorts), InitError> {
+                init_flow(create_ports)
+            }
+
+            #flow_ports
+
+            #flow_handle
+        }
+    }
+
+    /// Identifier of a node or port. Names that are not identifiers are rejected instead of panicking in `Ident::new`.
+    fn parse_ident(&self, name: &str, kind: &str) -> Result<Ident, Error> {
+        syn::parse_str::<Ident>(name).map_err(|err| anyhow::Error::msg(format!("Invalid {} name '{}': {}", kind, name, err)))
+    }
+
+    /// Names and types of all nodes, sorted by name.
+    fn emit_node_fields(&self, flow: &FlowModel, pm: &PackageManager) -> Result<(Vec<Ident>, Vec<syn::Type>), Error> {
+        let mut node_names: Vec<&String> = flow.nodes.keys().collect();
+        node_names.sort();
+
+        let mut node_idents = Vec::new();
+        let mut node_types = Vec::new();
+        for node_name in node_names {
+            let node = &flow.nodes[node_name];
+            let od = node.to_object_description(node_name, pm)?;
+            node_idents.push(self.parse_ident(node_name, "node")?);
+            node_types.push(od.object_type);
         }
+        Ok((node_idents, node_types))
     }
 
-    fn emit_init_function_body(&self, flow: &FlowModel, pm: &PackageManager) -> Result<TokenStream, Error> {
+    fn emit_create_nodes_body(&self, flow: &FlowModel, pm: &PackageManager, data_source: &JsonDataSource, node_idents: &[Ident]) -> Result<TokenStream, Error> {
         let mut body = TokenStream::new();
 
-        self.emit_std_locals(&mut body, flow);
+        // Nodes come first, so only the data that could not be embedded ends up in the data locals.
+        let mut nodes = TokenStream::new();
+        self.emit_nodes(flow, &mut nodes, pm, data_source)?;
+
+        self.emit_data_locals(&mut body, &self.get_runtime_data(flow, data_source));
 
-        self.emit_nodes(flow, &mut body, pm)?;
+        body.extend(nodes);
 
-        self.emit_node_connections(flow, &mut body);
+        self.emit_node_connections(flow, &mut body)?;
 
-        self.emit_flow(flow, &mut body);
+        body.extend(quote! {
+            Ok(FlowNodes { #(#node_idents),* })
+        });
+
+        Ok(body)
+    }
+
+    fn emit_init_function_body(&self, flow: &FlowModel, pm: &PackageManager, node_idents: &[Ident]) -> Result<TokenStream, Error> {
+        let mut body = TokenStream::new();
+
+        self.emit_std_locals(&mut body);
+
+        body.extend(quote! {
+            let mut nodes = create_nodes(change_observer, context, None)?;
+            let ports = create_ports(&mut nodes);
+            let FlowNodes { #(#node_idents),* } = nodes;
+        });
+
+        self.emit_flow(flow, &mut body, pm)?;
 
         self.emit_context_creation(&mut body);
 
         Ok(body)
     }
 
-    fn emit_nodes(&self, flow: &FlowModel, tokens: &mut TokenStream, pm: &PackageManager) -> Result<(), Error> {
-        for (node_name, node) in &flow.nodes {
-            let generated_code = self.emit_node(node_name, node, pm)?;
-            tokens.extend(generated_code);
+    fn get_flow_port_idents(&self, flow: &FlowModel, kind: &str, name: &str, port: &FlowPort) -> Result<(Ident, Ident, syn::Type), Error> {
+        if !flow.nodes.contains_key(&port.node) {
+            return Err(anyhow::Error::msg(format!("Flow {} '{}' refers to the unknown node '{}'.", kind, name, port.node)));
         }
-        Ok(())
+        let parse_error = |what: &str, err: syn::Error| anyhow::Error::msg(format!("Invalid {} of flow {} '{}': {}", what, kind, name, err));
+
+        let node_ident: Ident = syn::parse_str(&port.node).map_err(|err| parse_error("node", err))?;
+        let port_ident: Ident = syn::parse_str(&port.port).map_err(|err| parse_error("port", err))?;
+        let data_type: syn::Type = syn::parse_str(&port.data_type).map_err(|err| parse_error("type", err))?;
+        Ok((node_ident, port_ident, data_type))
     }
 
-    fn emit_node_connections(&self, flow: &FlowModel, tokens: &mut TokenStream) {
-        for connection in &flow.connections {
-            let generated_code = self.emit_node_connection(connection);
-            tokens.extend(generated_code);
+    /// Names and bindings of the flow inputs or outputs, sorted by name.
+    fn get_flow_ports(&self, flow: &FlowModel, kind: &str) -> Result<Vec<(String, Ident, Ident, syn::Type)>, Error> {
+        let ports = if kind == "input" { &flow.inputs } else { &flow.outputs };
+        let mut names: Vec<&String> = ports.keys().collect();
+        names.sort();
+
+        let mut result = Vec::new();
+        for name in names {
+            syn::parse_str::<Ident>(name)
+                .map_err(|err| anyhow::Error::msg(format!("Invalid name of flow {} '{}': {}", kind, name, err)))?;
+            let (node_ident, port_ident, data_type) = self.get_flow_port_idents(flow, kind, name, &ports[name])?;
+            result.push((name.clone(), node_ident, port_ident, data_type));
         }
+        Ok(result)
     }
 
-    fn node_model_to_object(&self, node_name: &String, node: &NodeModel, pm: &PackageManager) -> ObjectDescription {
-        ObjectDescription {
-            name: node_name.clone(),
-            type_name: node.node_type.clone(),
-            type_parameter_part: self.emit_type_parameter_part(&node, pm),
-            is_mutable: false,
-        }
+    fn format_ident(&self, prefix: &str, name: &str) -> Ident {
+        Ident::new(&format!("{}_{}", prefix, name), proc_macro2::Span::call_site())
     }
 
-    fn emit_type_parameter_part(&self, node: &NodeModel, pm: &PackageManager) -> String {
-        let mut tp_part = "".to_string();
-        if let Some(t) = pm.get_type(&node.node_type) {
-            if let Some(tp) = &t.type_parameters {
-                self.emit_type_parameter_part_rec(&tp, &node.type_parameters, pm, &mut tp_part);
-            }
+    /// Values of these types are passed to and from JavaScript directly, all others as JSON strings.
+    fn is_js_primitive(&self, data_type: &syn::Type) -> bool {
+        let js_primitives = ["i8", "i16", "i32", "u8", "u16", "u32", "f32", "f64", "bool", "String"];
+        matches!(data_type, syn::Type::Path(p) if js_primitives.iter().any(|t| p.path.is_ident(t)))
+    }
+
+    /// Values of these types are passed through the FFI directly, all others as JSON C strings.
+    fn is_ffi_primitive(&self, data_type: &syn::Type) -> bool {
+        let ffi_primitives = ["i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "f32", "f64", "bool"];
+        matches!(data_type, syn::Type::Path(p) if ffi_primitives.iter().any(|t| p.path.is_ident(t)))
+    }
+
+    /// `FlowPorts` holds a handle per flow input (the bound node input) and flow output (an input connected
+    /// to the bound node output), so hosts can exchange values with a running flow. The FFI accessors take
+    /// the handle returned by `native_init`; its ports are dropped when the flow is run to completion or cancelled.
+    /// Output values are queued until polled, so outputs that are never polled keep every value.
+    fn emit_flow_ports(&self, flow: &FlowModel) -> Result<TokenStream, Error> {
+        let inputs = self.get_flow_ports(flow, "input")?;
+        let outputs = self.get_flow_ports(flow, "output")?;
+
+        let mut fields = Vec::new();
+        let mut field_inits = Vec::new();
+        let mut output_connections = Vec::new();
+        let mut ffi_functions = Vec::new();
+
+        for (name, node_ident, port_ident, data_type) in &inputs {
+            let field_ident = self.format_ident("input", name);
+            let function_ident = self.format_ident("native_push", name);
+
+            fields.push(quote!(pub #field_ident: Input<#data_type>,));
+            field_inits.push(quote!(#field_ident: nodes.#node_ident.#port_ident.clone(),));
+
+            ffi_functions.push(if self.is_ffi_primitive(data_type) {
+                quote! {
+                    /// Returns false if the flow handle is unknown or the value cannot be sent.
+                    #[cfg(feature = "ffi")]
+                    #[no_mangle]
+                    pub extern "C" fn #function_ident(ctx_handle: *mut ExecutionContextHandle, value: #data_type) -> bool {
+                        with_flow_ports(ctx_handle, |ports| ports.#field_ident.send(value).is_ok()).unwrap_or(false)
+                    }
+                }
+            } else {
+                quote! {
+                    /// Takes the value as JSON. Returns false if the flow handle is unknown or the value cannot be parsed or sent.
+                    #[cfg(feature = "ffi")]
+                    #[no_mangle]
+                    pub unsafe extern "C" fn #function_ident(ctx_handle: *mut ExecutionContextHandle, value: *const c_char) -> bool {
+                        if value.is_null() {
+                            return false;
+                        }
+                        let value: #data_type = match serde_json::from_str(&CStr::from_ptr(value).to_string_lossy()) {
+                            Ok(value) => value,
+                            Err(_) => return false,
+                        };
+                        with_flow_ports(ctx_handle, |ports| ports.#field_ident.send(value).is_ok()).unwrap_or(false)
+                    }
+                }
+            });
         }
-        tp_part 
+
+        for (name, node_ident, port_ident, data_type) in &outputs {
+            let field_ident = self.format_ident("output", name);
+            let function_ident = self.format_ident("native_poll", name);
+
+            fields.push(quote!(pub #field_ident: Input<#data_type>,));
+            field_inits.push(quote!(#field_ident,));
+            output_connections.push(quote! {
+                let #field_ident = Input::new();
+                connect(&mut nodes.#node_ident.#port_ident, #field_ident.clone());
+            });
+
+            ffi_functions.push(if self.is_ffi_primitive(data_type) {
+                quote! {
+                    /// Writes the next value to `value`. Returns false if there is none.
+                    #[cfg(feature = "ffi")]
+                    #[no_mangle]
+                    pub unsafe extern "C" fn #function_ident(ctx_handle: *mut ExecutionContextHandle, value: *mut #data_type) -> bool {
+                        if value.is_null() {
+                            return false;
+                        }
+                        match with_flow_ports(ctx_handle, |ports| ports.#field_ident.next().ok()).flatten() {
+                            Some(next) => {
+                                *value = next;
+                                true
+                            }
+                            None => false,
+                        }
+                    }
+                }
+            } else {
+                quote! {
+                    /// Returns the next value as JSON (free it with `native_free_string`) or null if there is none.
+                    #[cfg(feature = "ffi")]
+                    #[no_mangle]
+                    pub extern "C" fn #function_ident(ctx_handle: *mut ExecutionContextHandle) -> *const c_char {
+                        with_flow_ports(ctx_handle, |ports| ports.#field_ident.next().ok())
+                            .flatten()
+                            .and_then(|value| serde_json::to_string(&value).ok())
+                            .and_then(|json| CString::new(json).ok())
+                            .map_or(std::ptr::null(), |json| json.into_raw())
+                    }
+                }
+            });
+        }
+
+        Ok(quote! {
+            pub struct FlowPorts {
+                #(#fields)*
+            }
+
+            #[allow(unused_variables)]
+            fn create_ports(nodes: &mut FlowNodes) -> FlowPorts {
+                #(#output_connections)*
+                FlowPorts { #(#field_inits)* }
+            }
+
+            /// Ports of the flows created by `native_init`, keyed by their handle.
+            #[cfg(feature = "ffi")]
+            static FLOW_PORTS: Mutex<Vec<(usize, FlowPorts)>> = Mutex::new(Vec::new());
+
+            #[cfg(feature = "ffi")]
+            fn with_flow_ports<R>(ctx_handle: *mut ExecutionContextHandle, f: impl FnOnce(&FlowPorts) -> R) -> Option<R> {
+                FLOW_PORTS.lock().unwrap().iter().find(|(handle, _)| *handle == ctx_handle as usize).map(|(_, ports)| f(ports))
+            }
+
+            #[cfg(feature = "ffi")]
+            fn remove_flow_ports(ctx_handle: *mut ExecutionContextHandle) {
+                FLOW_PORTS.lock().unwrap().retain(|(handle, _)| *handle != ctx_handle as usize);
+            }
+
+            #(#ffi_functions)*
+        })
     }
 
-    fn emit_type_parameter_part_rec(&self, type_parameters: &Vec<String>, resolved_type_parameters: &HashMap<String, String>, pm: &PackageManager, tp_part: &mut String) {
-        
-        if !type_parameters.is_empty() {
-            tp_part.push_str("<");
+    /// `FlowHandle` lets JavaScript drive the flow step by step (e.g. from requestAnimationFrame),
+    /// push values into the flow inputs and subscribe to or poll the flow outputs.
+    fn emit_flow_handle(&self, flow: &FlowModel, node_idents: &Vec<Ident>) -> Result<TokenStream, Error> {
+        let inputs = self.get_flow_ports(flow, "input")?;
+        let outputs = self.get_flow_ports(flow, "output")?;
+
+        let js_error = quote!(|err| JsValue::from_str(&err.to_string()));
+
+        let mut push_functions = Vec::new();
+        for (name, _, _, data_type) in &inputs {
+            let field_ident = self.format_ident("input", name);
+            let function_ident = self.format_ident("push", name);
+
+            push_functions.push(if self.is_js_primitive(data_type) {
+                quote! {
+                    pub fn #function_ident(&mut self, value: #data_type) -> Result<(), JsValue> {
+                        self.ports.#field_ident.send(value).map_err(|err| JsValue::from_str(&format!("{:?}", err)))
+                    }
+                }
+            } else {
+                quote! {
+                    /// Takes the value as JSON.
+                    pub fn #function_ident(&mut self, value: &str) -> Result<(), JsValue> {
+                        let value: #data_type = serde_json::from_str(value).map_err(#js_error)?;
+                        self.ports.#field_ident.send(value).map_err(|err| JsValue::from_str(&format!("{:?}", err)))
+                    }
+                }
+            });
         }
 
-        for type_parameter in type_parameters {
+        let mut subscriber_fields = Vec::new();
+        let mut subscriber_inits = Vec::new();
+        let mut output_functions = Vec::new();
+        let mut output_dispatches = Vec::new();
+        for (name, _, _, data_type) in &outputs {
+            let field_ident = self.format_ident("output", name);
+            let subscribers_ident = self.format_ident("subscribers", name);
+            let subscribe_ident = self.format_ident("subscribe", name);
+            let poll_ident = self.format_ident("poll", name);
 
-            if let Some(type_name) = resolved_type_parameters.get(type_parameter) {
+            subscriber_fields.push(quote!(#subscribers_ident: Vec<js_sys::Function>,));
+            subscriber_inits.push(quote!(#subscribers_ident: Vec::new(),));
 
-                tp_part.push_str(type_name);
-                if let Some(t) = pm.get_type(type_name) {
-                    if let Some(tps) = &t.type_parameters {
-                        self.emit_type_parameter_part_rec(tps, resolved_type_parameters, pm, tp_part);  
-                    }      
-                } 
-                tp_part.push_str(",");
+            let (js_value, poll_function) = if self.is_js_primitive(data_type) {
+                (
+                    quote!(JsValue::from(value)),
+                    quote! {
+                        pub fn #poll_ident(&mut self) -> Option<#data_type> {
+                            self.ports.#field_ident.next().ok()
+                        }
+                    },
+                )
             } else {
-                //TODO: Error Reporting. 
+                (
+                    quote!(JsValue::from_str(&serde_json::to_string(&value).map_err(#js_error)?)),
+                    quote! {
+                        /// Returns the value as JSON.
+                        pub fn #poll_ident(&mut self) -> Result<Option<String>, JsValue> {
+                            self.ports.#field_ident.next().ok().map(|value| serde_json::to_string(&value).map_err(#js_error)).transpose()
+                        }
+                    },
+                )
+            };
+
+            output_functions.push(quote! {
+                /// Subscribed outputs pass their values to the subscribers after each step instead of queueing them for polling.
+                pub fn #subscribe_ident(&mut self, callback: js_sys::Function) {
+                    self.#subscribers_ident.push(callback);
+                }
+
+                /// Returns the next value of the output, if it has no subscribers. Values are queued until polled.
+                #poll_function
+            });
+            output_dispatches.push(quote! {
+                if !self.#subscribers_ident.is_empty() {
+                    while let Ok(value) = self.ports.#field_ident.next() {
+                        let value = #js_value;
+                        for subscriber in &self.#subscribers_ident {
+                            subscriber.call1(&JsValue::NULL, &value)?;
+                        }
+                    }
+                }
+            });
+        }
+
+        Ok(quote! {
+            #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+            #[wasm_bindgen]
+            pub struct FlowHandle {
+                nodes: FlowNodes,
+                ports: FlowPorts,
+                change_observer: ChangeObserver,
+                cancelled: bool,
+                #(#subscriber_fields)*
             }
+
+            #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+            #[wasm_bindgen]
+            impl FlowHandle {
+                /// Creates, initializes and readies all nodes. `data_override` is a JSON object replacing parts of the flow data.
+                pub fn init(data_override: Option<String>) -> Result<FlowHandle, JsValue> {
+                    let change_observer = ChangeObserver::new();
+                    let context = Arc::new(Mutex::new(Context::new()));
+                    let mut nodes = create_nodes(Some(&change_observer), context, data_override.as_deref())
+                        .map_err(#js_error)?;
+                    let ports = create_ports(&mut nodes);
+
+                    #(nodes.#node_idents.on_init().map_err(|err| JsValue::from_str(&format!("{:?}", err)))?;)*
+                    #(nodes.#node_idents.on_ready().map_err(|err| JsValue::from_str(&format!("{:?}", err)))?;)*
+
+                    Ok(FlowHandle { nodes, ports, change_observer, cancelled: false, #(#subscriber_inits)* })
+                }
+
+                /// Updates every node once and passes new output values to the subscribers.
+                pub fn step(&mut self) -> Result<(), JsValue> {
+                    if self.cancelled {
+                        return Ok(());
+                    }
+
+                    #(self.nodes.#node_idents.on_update().map_err(|err| JsValue::from_str(&format!("{:?}", err)))?;)*
+                    #(#output_dispatches)*
+
+                    Ok(())
+                }
+
+                /// Steps for about `ms` milliseconds, at least once. Returns the number of steps.
+                pub fn run_for(&mut self, ms: f64) -> Result<u32, JsValue> {
+                    let start = js_sys::Date::now();
+                    let mut steps = 0;
+                    loop {
+                        self.step()?;
+                        steps += 1;
+                        if self.cancelled || js_sys::Date::now() - start >= ms {
+                            return Ok(steps);
+                        }
+                    }
+                }
+
+                /// Shuts all nodes down. Further steps do nothing.
+                pub fn cancel(&mut self) -> Result<(), JsValue> {
+                    if self.cancelled {
+                        return Ok(());
+                    }
+                    self.cancelled = true;
+
+                    #(self.nodes.#node_idents.on_shutdown().map_err(|err| JsValue::from_str(&format!("{:?}", err)))?;)*
+
+                    Ok(())
+                }
+
+                pub fn is_cancelled(&self) -> bool {
+                    self.cancelled
+                }
+
+                #(#push_functions)*
+
+                #(#output_functions)*
+            }
+        })
+    }
+
+    fn emit_nodes(&self, flow: &FlowModel, tokens: &mut TokenStream, pm: &PackageManager, data_source: &JsonDataSource) -> Result<(), Error> {
+        for (node_name, node) in &flow.nodes {
+            let generated_code = self.emit_node(node_name, node, pm, data_source)?;
+            tokens.extend(generated_code);
         }
-        if !type_parameters.is_empty() {
-            //tp_part.pop(); // pop last ,
-            tp_part.push_str(">");
+        Ok(())
+    }
+
+    fn emit_node_connections(&self, flow: &FlowModel, tokens: &mut TokenStream) -> Result<(), Error> {
+        for connection in &flow.connections {
+            let generated_code = self.emit_node_connection(connection)?;
+            tokens.extend(generated_code);
         }
+        Ok(())
     }
 
-    fn emit_node(&self, node_name: &str, node: &NodeModel, pm: &PackageManager) -> Result<TokenStream, Error>  {
+    fn emit_node(&self, node_name: &str, node: &NodeModel, pm: &PackageManager, data_source: &JsonDataSource) -> Result<TokenStream, Error>  {
         if let Some(node_type) = pm.get_type(&node.node_type) {
 
             if let Some(constructor) = node_type.constructors.get(&node.constructor) {
                 
-                let res = constructor.emit_code_template(
-                    &self.node_model_to_object(&node_name.to_string(), node, pm),
+                constructor.emit_code_template(
+                    &node.to_object_description(node_name, pm)?,
                     &node.type_parameters,
                     pm,
                     &Namespace::new(),
-                );
-
-                match res {
-                    Ok(code)=> { 
-                        let tok: TokenStream = code.parse().unwrap();
-                        Ok(quote! {
-                            #tok
-                        }) 
-                    },
-                    Err(err) => {
-                        Err(err)
-                    }
-                }
+                    data_source,
+                )
             } else {
                 Err(anyhow::Error::msg(format!("Cannot find constructor '{}' for node '{}' with type '{}'", node.constructor, node_name, node.node_type)))
             }
@@ -214,63 +889,127 @@ impl StandardCodeEmitter {
        
     }
 
-    fn emit_node_connection(&self, connection: &ConnectionModel) -> TokenStream {
-        // TODO: does quote require the variables to be mutable?
-        let mut node_out_ident = Ident::new(&connection.from_node, proc_macro2::Span::call_site());
-        let node_inp_ident = Ident::new(&connection.to_node, proc_macro2::Span::call_site());
-        let output_ident = Ident::new(&connection.from_output, proc_macro2::Span::call_site());
-        let input_ident = Ident::new(&connection.to_input, proc_macro2::Span::call_site());
+    fn emit_node_connection(&self, connection: &ConnectionModel) -> Result<TokenStream, Error> {
+        let node_out_ident = self.parse_ident(&connection.from_node, "node")?;
+        let node_inp_ident = self.parse_ident(&connection.to_node, "node")?;
+        let output_ident = self.parse_ident(&connection.from_output, "output")?;
+        let input_ident = self.parse_ident(&connection.to_input, "input")?;
 
-        quote! {
+        Ok(quote! {
             connect(&mut #node_out_ident.#output_ident, #node_inp_ident.#input_ident.clone());
-        }
+        })
     }
 
-    fn emit_std_locals(&self, tokens: &mut TokenStream, flow: &FlowModel) {
-
-        let data_str = serde_json::to_string(&flow.data).unwrap();
+    fn emit_std_locals(&self, tokens: &mut TokenStream) {
 
         tokens.extend(quote! {
             let co = ChangeObserver::new();
             let change_observer = Some(&co);
             let context = Arc::new(Mutex::new(Context::new()));
-            let data_str = #data_str;
-            let data: Value = serde_json::from_str(&data_str).expect("Failed to parse flow project data.");
         });
     }
 
-    fn emit_flow(&self, flow: &FlowModel, tokens: &mut TokenStream) {
+    fn emit_data_locals(&self, tokens: &mut TokenStream, runtime_data: &Option<Value>) {
+        if let Some(runtime_data) = runtime_data {
+            let data_str = serde_json::to_string(runtime_data).unwrap();
+            tokens.extend(quote! {
+                let data_str = #data_str;
+                let mut data: Value = serde_json::from_str(&data_str).map_err(|err| InitError::new(format!("Failed to parse flow project data: {}", err)))?;
+                if let Some(data_override) = data_override {
+                    let data_override: Value = serde_json::from_str(data_override).map_err(|err| InitError::new(format!("Failed to parse data override: {}", err)))?;
+                    if let (Some(data), Value::Object(data_override)) = (data.as_object_mut(), data_override) {
+                        data.extend(data_override);
+                    }
+                }
+            });
+        }
+    }
+
+    fn get_data_source<'a>(&self, flow: &'a FlowModel) -> JsonDataSource<'a> {
+        if self.options.embed_data {
+            JsonDataSource::Embedded { data: &flow.data, fallbacks: RefCell::new(Vec::new()) }
+        } else {
+            JsonDataSource::Runtime
+        }
+    }
+
+    /// Flow data parsed from JSON at runtime. None if all data is embedded.
+    fn get_runtime_data(&self, flow: &FlowModel, data_source: &JsonDataSource) -> Option<Value> {
+        match data_source {
+            JsonDataSource::Runtime => Some(flow.data.clone()),
+            JsonDataSource::Literal { .. } => Option::None,
+            JsonDataSource::Embedded { fallbacks, .. } => {
+                if fallbacks.borrow().is_empty() {
+                    return Option::None;
+                }
+
+                // Only keep the data of objects that are deserialized at runtime.
+                let mut runtime_data = Value::Object(serde_json::Map::new());
+                for keys in fallbacks.borrow().iter() {
+                    if let Some(value) = keys.iter().try_fold(&flow.data, |value, key| value.get(key)) {
+                        let mut target = &mut runtime_data;
+                        for key in keys {
+                            target = &mut target[key.as_str()];
+                        }
+                        *target = value.clone();
+                    }
+                }
+                Some(runtime_data)
+            }
+        }
+    }
+
+    fn emit_node_description(&self, node_name: &str, node: &NodeModel, pm: &PackageManager) -> (String, String) {
+        let name = node.label.clone().unwrap_or(node_name.to_string());
+
+        let description = node
+            .description
+            .clone()
+            .or_else(|| pm.get_type(&node.node_type).and_then(|t| t.documentation.clone()))
+            .unwrap_or(node_name.to_string());
+
+        (name, description)
+    }
+
+    fn emit_flow(&self, flow: &FlowModel, tokens: &mut TokenStream, pm: &PackageManager) -> Result<(), Error> {
         tokens.extend(quote! {
             let mut flow = Flow::new_empty();
         });
 
-        let mut id: u128 = 0;
-        for (node_name, node) in &flow.nodes {
-            let node_ident = Ident::new(&node_name, proc_macro2::Span::call_site());
+        for (id, (node_name, node)) in flow.nodes.iter().enumerate() {
+            let id = id as u128;
+            let node_ident = self.parse_ident(node_name, "node")?;
             let node_type = node.node_type.clone();
+            let (name, description) = self.emit_node_description(node_name, node, pm);
             tokens.extend(quote! {
                 flow.add_node_with_id_and_desc(
                     #node_ident,
                     #id,
-                    NodeDescription {name: #node_name.into(), description: #node_name.into() /*TODO: get a node desc.*/, kind: #node_type.into()});
+                    NodeDescription {name: #name.into(), description: #description.into(), kind: #node_type.into()});
             });
-            id += 1;
         }
+        Ok(())
     }
 
-    fn emit_use_decls(&self) -> TokenStream {
+    fn emit_use_decls(&self, uses_json: bool) -> TokenStream {
+        let json_use_decl = if uses_json { quote!(use serde_json::Value;) } else { quote!() };
         quote! {
             use flowrs::exec::execution::{Executor, StandardExecutor, ExecutionContext, ExecutionContextHandle};
             use flowrs::exec::node_updater::{NodeUpdater, SingleThreadedNodeUpdater, MultiThreadedNodeUpdater};
             use flowrs::flow::flow::Flow;
-            use flowrs::nodes::connection::connect;
+            use flowrs::nodes::connection::{connect, Input};
             use flowrs::nodes::node::{ChangeObserver, Context};
             use flowrs::nodes::node_description::NodeDescription;
             use flowrs::sched::{scheduler::Scheduler, round_robin::RoundRobinScheduler};
-            use serde_json::Value;
+            #json_use_decl
             use std::sync::{Arc, Mutex};
+            #[cfg(feature = "ffi")]
             use std::ffi::{CString, CStr};
+            #[cfg(feature = "ffi")]
             use std::os::raw::c_char;
+            #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+            use flowrs::nodes::node::Node;
+            #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
             use wasm_bindgen::prelude::*;
         }
     }
@@ -278,22 +1017,124 @@ impl StandardCodeEmitter {
     fn emit_context_creation(&self, tokens: &mut TokenStream) {
         tokens.extend(quote! {
             let executor = StandardExecutor::new(co);
-            ExecutionContext::new(executor, flow)
+            Ok((ExecutionContext::new(executor, flow), ports))
         });
     }
 }
 
 impl CodeEmitter for StandardCodeEmitter {
     fn emit_flow_code(&self, flow: &FlowModel, pm: &PackageManager) -> Result<String, Error> {
-        
-        let init_function_body = self.emit_init_function_body(flow, pm)?;
+
+        let data_source = self.get_data_source(flow);
+        let (node_idents, node_types) = self.emit_node_fields(flow, pm)?;
+        let create_nodes_body = self.emit_create_nodes_body(flow, pm, &data_source, &node_idents)?;
+        let init_function_body = self.emit_init_function_body(flow, pm, &node_idents)?;
+        let flow_ports = self.emit_flow_ports(flow)?;
+        let flow_handle = self.emit_flow_handle(flow, &node_idents)?;
         
         Ok(format!(
             "{}{}",
-            self.emit_use_decls(),
-            self.emit_functions(&init_function_body)
+            self.emit_use_decls(self.get_runtime_data(flow, &data_source).is_some()),
+            self.emit_functions(&node_idents, &node_types, &create_nodes_body, &init_function_body, &flow_ports, &flow_handle)
         ))
     }
+
+    /// serde_json parses the runtime data and passes port values that are not FFI or JavaScript primitives.
+    fn get_dependencies(&self, flow: &FlowModel, pm: &PackageManager) -> Result<Vec<String>, Error> {
+        let data_source = self.get_data_source(flow);
+        let (node_idents, _) = self.emit_node_fields(flow, pm)?;
+        self.emit_create_nodes_body(flow, pm, &data_source, &node_idents)?;
+
+        let mut ports = self.get_flow_ports(flow, "input")?;
+        ports.extend(self.get_flow_ports(flow, "output")?);
+
+        let uses_json = self.get_runtime_data(flow, &data_source).is_some()
+            || ports.iter().any(|(_, _, _, data_type)| !self.is_ffi_primitive(data_type) || !self.is_js_primitive(data_type));
+
+        Ok(if uses_json { vec!["serde_json".to_string()] } else { Vec::new() })
+    }
+}
+
+/// Emits `#![no_std]` code for embedded targets. There is no executor, wasm or FFI glue:
+/// `init` returns all nodes by value in a `FlowNodes` struct (no heap allocation by the flow itself)
+/// and flow data is converted to Rust literals at build time instead of being parsed at runtime.
+/// All node types must come from packages marked `no_std`.
+pub struct NoStdCodeEmitter {}
+
+impl NoStdCodeEmitter {
+    fn check_no_std_packages(&self, flow: &FlowModel, pm: &PackageManager) -> Result<(), Error> {
+        for (node_name, node) in &flow.nodes {
+            let type_names = std::iter::once(&node.node_type).chain(node.type_parameters.values());
+            for type_name in type_names {
+                if let Some(package) = pm.get_package_of_type(type_name) {
+                    if !package.no_std {
+                        return Err(anyhow::Error::msg(format!(
+                            "Node '{}' uses type '{}' of package '{}', which is not marked no_std.",
+                            node_name, type_name, package.name
+                        )));
+                    }
+                }
+            }
+
+            let constructor = pm.get_type(&node.node_type).and_then(|t| t.constructors.get(&node.constructor));
+            if let Some(Constructor::NewWithObserverAndContext { .. }) = constructor {
+                return Err(anyhow::Error::msg(format!(
+                    "Node '{}' needs a context, which is not available in no_std flows.",
+                    node_name
+                )));
+            }
+
+            if let Some(constructor) = constructor {
+                let obj_desc = node.to_object_description(node_name, pm)?;
+                constructor.check_no_std(&obj_desc, &node.type_parameters, pm).map_err(|err| {
+                    anyhow::Error::msg(format!("Node '{}' cannot be built without std: {}", node_name, err))
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CodeEmitter for NoStdCodeEmitter {
+    fn emit_flow_code(&self, flow: &FlowModel, pm: &PackageManager) -> Result<String, Error> {
+        self.check_no_std_packages(flow, pm)?;
+
+        let standard = StandardCodeEmitter::default();
+        let data_source = JsonDataSource::Literal { data: &flow.data, allow_alloc: false };
+
+        let (node_idents, node_types) = standard.emit_node_fields(flow, pm)?;
+
+        let mut body = TokenStream::new();
+        for node_ident in &node_idents {
+            let node_name = node_ident.to_string();
+            body.extend(standard.emit_node(&node_name, &flow.nodes[&node_name], pm, &data_source)?);
+        }
+
+        for connection in &flow.connections {
+            body.extend(standard.emit_node_connection(connection)?);
+        }
+
+        Ok(quote! {
+            #![no_std]
+
+            use flowrs::nodes::connection::connect;
+            use flowrs::nodes::node::ChangeObserver;
+
+            pub struct FlowNodes {
+                #(pub #node_idents: #node_types,)*
+            }
+
+            #[allow(unused_variables)]
+            pub fn init(change_observer: Option<&ChangeObserver>) -> FlowNodes {
+                #body
+                FlowNodes { #(#node_idents),* }
+            }
+        }
+        .to_string())
+    }
+    fn is_no_std(&self) -> bool {
+        true
+    }
 }
 
 #[test]
@@ -366,17 +1207,363 @@ fn test() {
     }
     "#;
 
-    let flow_model: FlowModel = serde_json::from_str(&flow_json).expect("wrong format.");
+    let flow_model: FlowModel = serde_json::from_str(flow_json).expect("wrong format.");
 
     let mut pm = PackageManager::new();
 
-    let p: Package = serde_json::from_str(package_json).expect("format wrong.");
+    let p: package::Package = serde_json::from_str(package_json).expect("format wrong.");
 
     pm.add_package(p);
 
-    let rce = StandardCodeEmitter {};
+    let rce = StandardCodeEmitter::default();
     println!("{}", rce.emit_flow_code(&flow_model, &pm).expect("flow code wrong."));
 
     //let pack = StandardWasmPackager::new(rce);
     //pack.compile_package(&flow_model);
 }
+
+/// Builds flows of flowrs-std nodes for tests.
+#[cfg(test)]
+struct TestFlowBuilder {
+    flow: Value,
+}
+
+#[cfg(test)]
+impl TestFlowBuilder {
+    fn new() -> Self {
+        Self { flow: serde_json::json!({"nodes": {}, "connections": [], "data": {}}) }
+    }
+
+    /// Adds a node of type `flowrs_std::nodes::<node_type>` with type parameter `I` set to `type_argument`.
+    fn node(mut self, name: &str, node_type: &str, type_argument: &str) -> Self {
+        self.flow["nodes"][name] = serde_json::json!({
+            "node_type": format!("flowrs_std::nodes::{}", node_type),
+            "type_parameters": {"I": type_argument},
+            "constructor": "New"
+        });
+        self
+    }
+
+    /// Connects `output` of `from_node` to `input` of `to_node`.
+    fn connect(mut self, from_node: &str, to_node: &str) -> Self {
+        let connection = serde_json::json!({"from_node": from_node, "from_output": "output", "to_node": to_node, "to_input": "input"});
+        self.flow["connections"].as_array_mut().unwrap().push(connection);
+        self
+    }
+
+    /// Binds flow input or output `name` (`kind` is "inputs" or "outputs") to the port of the same kind of `node`.
+    fn port(mut self, kind: &str, name: &str, node: &str, data_type: &str) -> Self {
+        let port = if kind == "inputs" { "input" } else { "output" };
+        self.flow[kind][name] = serde_json::json!({"node": node, "port": port, "type": data_type});
+        self
+    }
+
+    fn data(mut self, data: Value) -> Self {
+        self.flow["data"] = data;
+        self
+    }
+
+    fn build(self) -> FlowModel {
+        serde_json::from_value(self.flow).expect("wrong format.")
+    }
+}
+
+/// Package manager with flowrs-std, optionally marked no_std.
+#[cfg(test)]
+fn test_package_manager(no_std: bool) -> PackageManager {
+    let mut package: package::Package = serde_json::from_str(include_str!("../flow-packages/flowrs-std.json")).expect("wrong format.");
+    package.no_std = no_std;
+    let mut pm = PackageManager::new();
+    pm.add_package(package);
+    pm
+}
+
+/// Signatures of all functions and methods of the generated code, without whitespace.
+#[cfg(test)]
+fn get_signatures(file: &syn::File) -> Vec<String> {
+    struct SignatureCollector(Vec<String>);
+
+    impl<'ast> syn::visit::Visit<'ast> for SignatureCollector {
+        fn visit_signature(&mut self, signature: &'ast syn::Signature) {
+            self.0.push(package::compact_code(&quote!(#signature).to_string()));
+        }
+    }
+
+    let mut collector = SignatureCollector(Vec::new());
+    syn::visit::visit_file(&mut collector, file);
+    collector.0
+}
+
+#[cfg(test)]
+fn assert_signature(file: &syn::File, signature: &str) {
+    let signatures = get_signatures(file);
+    assert!(signatures.contains(&package::compact_code(signature)), "'{}' not in {:?}", signature, signatures);
+}
+
+#[test]
+fn test_validate_data() {
+    let pm = test_package_manager(false);
+    let mut flow_model = TestFlowBuilder::new()
+        .node("token_node", "value::ValueNode", "u8")
+        .data(serde_json::json!({"token_node": {"value": 42}}))
+        .build();
+    assert!(flow_model.validate_data(&pm).is_empty());
+
+    flow_model.data = serde_json::json!({"token_node": {"value": 256}});
+    let issues = flow_model.validate_data(&pm);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].path, "$.data.token_node.value");
+
+    flow_model.data = serde_json::json!({});
+    assert_eq!(flow_model.validate_data(&pm).len(), 1);
+}
+
+#[test]
+fn test_emit_flow_code() {
+    let pm = test_package_manager(false);
+    let mut flow_model = TestFlowBuilder::new()
+        .node("token_node", "value::ValueNode", "i32")
+        .node("debug_node", "debug::DebugNode", "i32")
+        .connect("token_node", "debug_node")
+        .data(serde_json::json!({"token_node": {"value": 42}}))
+        .build();
+    let debug_node = flow_model.nodes.get_mut("debug_node").unwrap();
+    debug_node.label = Some("Printer".to_string());
+    debug_node.tags = vec!["debug".to_string()];
+
+    let code = StandardCodeEmitter::default().emit_flow_code(&flow_model, &pm).expect("flow code wrong.");
+    let file = syn::parse_file(&code).expect("generated code does not parse.");
+
+    assert_signature(&file, "fn init() -> Result<ExecutionContext, InitError>");
+    assert_signature(&file, "fn run_native(mut ctx: ExecutionContext, num_workers: usize) -> String");
+    assert_signature(&file, "extern \"C\" fn native_last_error() -> *const c_char");
+    let native_init = file
+        .items
+        .iter()
+        .find_map(|item| match item {
+            syn::Item::Fn(f) if f.sig.ident == "native_init" => Some(f),
+            _ => Option::None,
+        })
+        .expect("native_init missing.");
+    assert!(native_init.attrs.iter().any(|attr| attr.path.is_ident("no_mangle")));
+    assert!(native_init.attrs.iter().any(|attr| attr.path.is_ident("cfg") && attr.tokens.to_string().contains("ffi")));
+
+    // The label is the name, the description is the type documentation alone. Tags are editor metadata.
+    let debug_type = &flow_model.nodes["debug_node"].node_type;
+    let documentation = pm.get_type(debug_type).and_then(|t| t.documentation.clone()).expect("documentation missing.");
+    assert_eq!(documentation, "Prints every value it receives on its input and forwards it to its output.");
+    let description = quote!(NodeDescription {name: "Printer".into(), description: #documentation.into(), kind: #debug_type.into()});
+    assert!(package::compact_code(&code).contains(&package::compact_code(&description.to_string())));
+    assert!(!code.contains("Failed to parse flow project data.\")"));
+
+    flow_model.nodes.insert("1st".to_string(), flow_model.nodes["token_node"].clone());
+    assert!(StandardCodeEmitter::default().emit_flow_code(&flow_model, &pm).is_err());
+    flow_model.nodes.remove("1st");
+    flow_model.connections[0].to_node = "my-node".to_string();
+    assert!(StandardCodeEmitter::default().emit_flow_code(&flow_model, &pm).is_err());
+}
+
+#[test]
+fn test_emit_flow_handle() {
+    let pm = test_package_manager(false);
+    let mut flow_model = TestFlowBuilder::new()
+        .node("debug_node", "debug::DebugNode", "i32")
+        .node("json_debug_node", "debug::DebugNode", "Vec<i32>")
+        .port("inputs", "token", "debug_node", "i32")
+        .port("inputs", "tokens", "json_debug_node", "Vec<i32>")
+        .port("outputs", "result", "debug_node", "i32")
+        .build();
+
+    let code = StandardCodeEmitter::default().emit_flow_code(&flow_model, &pm).expect("flow code wrong.");
+    let file = syn::parse_file(&code).expect("generated code does not parse.");
+
+    assert!(file.items.iter().any(|item| matches!(item, syn::Item::Struct(s) if s.ident == "FlowHandle")));
+    assert_signature(&file, "fn run_for(&mut self, ms: f64) -> Result<u32, JsValue>");
+    assert_signature(&file, "fn push_token(&mut self, value: i32) -> Result<(), JsValue>");
+    assert_signature(&file, "fn push_tokens(&mut self, value: &str) -> Result<(), JsValue>");
+    assert_signature(&file, "fn subscribe_result(&mut self, callback: js_sys::Function)");
+
+    flow_model.outputs.get_mut("result").unwrap().node = "missing_node".to_string();
+    assert!(StandardCodeEmitter::default().emit_flow_code(&flow_model, &pm).is_err());
+}
+
+#[test]
+fn test_flow_ports() {
+    let pm = test_package_manager(false);
+    let mut flow_model = TestFlowBuilder::new()
+        .node("debug_node", "debug::DebugNode", "i32")
+        .node("json_debug_node", "debug::DebugNode", "Vec<i32>")
+        .port("inputs", "token", "debug_node", "i32")
+        .port("outputs", "result", "debug_node", "i32")
+        .port("outputs", "results", "json_debug_node", "Vec<i32>")
+        .build();
+    assert!(flow_model.validate_ports(&pm).is_empty());
+
+    let code = StandardCodeEmitter::default().emit_flow_code(&flow_model, &pm).expect("flow code wrong.");
+    let file = syn::parse_file(&code).expect("generated code does not parse.");
+
+    let flow_ports = file
+        .items
+        .iter()
+        .find_map(|item| match item {
+            syn::Item::Struct(s) if s.ident == "FlowPorts" => Some(s),
+            _ => Option::None,
+        })
+        .expect("FlowPorts missing.");
+    let fields: Vec<String> = flow_ports.fields.iter().map(|field| package::compact_code(&quote!(#field).to_string())).collect();
+    assert_eq!(fields, vec!["pubinput_token:Input<i32>", "puboutput_result:Input<i32>", "puboutput_results:Input<Vec<i32>>"]);
+
+    assert_signature(&file, "fn init_with_ports() -> Result<(ExecutionContext, FlowPorts), InitError>");
+    assert_signature(&file, "extern \"C\" fn native_push_token(ctx_handle: *mut ExecutionContextHandle, value: i32) -> bool");
+    assert_signature(&file, "unsafe extern \"C\" fn native_poll_result(ctx_handle: *mut ExecutionContextHandle, value: *mut i32) -> bool");
+    assert_signature(&file, "extern \"C\" fn native_poll_results(ctx_handle: *mut ExecutionContextHandle) -> *const c_char");
+    assert_signature(&file, "fn with_flow_ports<R>(ctx_handle: *mut ExecutionContextHandle, f: impl FnOnce(&FlowPorts) -> R) -> Option<R>");
+    assert_signature(&file, "fn remove_flow_ports(ctx_handle: *mut ExecutionContextHandle)");
+    assert_signature(&file, "fn poll_result(&mut self) -> Option<i32>");
+
+    flow_model.inputs.insert("result".to_string(), flow_model.inputs["token"].clone());
+    flow_model.inputs.get_mut("token").unwrap().port = "output".to_string();
+    flow_model.outputs.get_mut("results").unwrap().node = "missing_node".to_string();
+    flow_model.outputs.get_mut("result").unwrap().data_type = "Vec<".to_string();
+
+    let paths: Vec<String> = flow_model.validate_ports(&pm).into_iter().map(|issue| issue.path).collect();
+    assert_eq!(paths, vec!["$.inputs.token.port", "$.outputs.result", "$.outputs.result.type", "$.outputs.results.node"]);
+}
+
+#[test]
+fn test_emit_flow_code_with_embedded_data() {
+    let pm = test_package_manager(false);
+    let mut flow_model = TestFlowBuilder::new()
+        .node("token_node", "value::ValueNode", "i32")
+        .node("timer_config_node", "value::ValueNode", "flowrs_std::nodes::timer::TimerNodeConfig")
+        .data(serde_json::json!({
+            "token_node": {"value": 42},
+            "timer_config_node": {"value": {"duration": {"secs": 1, "nanos": 0}}}
+        }))
+        .build();
+    let emitter = StandardCodeEmitter { options: StandardEmitterOptions { embed_data: true } };
+
+    // TimerNodeConfig does not declare its fields, so only its data is parsed at runtime.
+    let code = emitter.emit_flow_code(&flow_model, &pm).expect("flow code wrong.");
+    let file = syn::parse_file(&code).expect("generated code does not parse.");
+    assert!(code.contains("42i32"));
+    assert!(file.items.iter().any(|item| matches!(item, syn::Item::Use(u) if quote!(#u).to_string().contains("serde_json"))));
+    assert!(code.contains("timer_config_node"));
+    assert!(!code.contains("\\\"token_node\\\""));
+
+    flow_model.nodes.remove("timer_config_node");
+    let code = emitter.emit_flow_code(&flow_model, &pm).expect("flow code wrong.");
+    assert!(code.contains("42i32"));
+    assert!(!code.contains("serde_json"));
+
+    // Data that does not fit its type fails the build instead of failing at runtime.
+    flow_model.data = serde_json::json!({"token_node": {"value": 1099511627776u64}});
+    let err = emitter.emit_flow_code(&flow_model, &pm).unwrap_err();
+    assert!(format!("{:#}", err).contains("$.token_node.value"));
+}
+
+#[test]
+fn test_emit_no_std_flow_code() {
+    let mut pm = test_package_manager(true);
+    let mut flow_model = TestFlowBuilder::new()
+        .node("token_node", "value::ValueNode", "i32")
+        .node("debug_node", "debug::DebugNode", "i32")
+        .connect("token_node", "debug_node")
+        .data(serde_json::json!({"token_node": {"value": 42}}))
+        .build();
+
+    let code = NoStdCodeEmitter {}.emit_flow_code(&flow_model, &pm).expect("flow code wrong.");
+    let file = syn::parse_file(&code).expect("generated code does not parse.");
+    assert!(file.attrs.iter().any(|attr| attr.path.is_ident("no_std")));
+    assert_eq!(get_signatures(&file), vec![package::compact_code("fn init(change_observer: Option<&ChangeObserver>) -> FlowNodes")]);
+    assert!(code.contains("42i32"));
+    assert!(!code.contains("serde_json"));
+    assert!(!code.contains("wasm_bindgen"));
+
+    // Pointer wrappers, expressions and allocating literals need std or alloc. Errors name the node.
+    pm.add_package(serde_json::from_str(r#"
+    {
+        "name":"my_package",
+        "version":"1.0.0",
+        "no_std":true,
+        "crates":{
+           "my_crate":{
+              "types":{
+                 "Filter":{"constructors":{"Default":"FromDefault"}},
+                 "FilterNode":{
+                    "constructors":{
+                       "New":{
+                          "NewWithArbitraryArgs":{
+                             "arguments":[
+                                {
+                                   "type":{"Type":{"name":"my_crate::Filter"}},
+                                   "name":"filter",
+                                   "passing":"Move",
+                                   "construction":{"Constructor":"Default"},
+                                   "wrapping":["Box"]
+                                }
+                             ]
+                          }
+                       }
+                    }
+                 }
+              },
+              "modules":{}
+           }
+        }
+    }
+    "#).expect("wrong format."));
+    let rejected_nodes = [
+        ("filter_node", r#"{"node_type": "my_crate::FilterNode", "type_parameters": {}, "constructor": "New"}"#),
+        (
+            "token_node",
+            r#"{"node_type": "flowrs_std::nodes::value::ValueNode", "type_parameters": {"I": "i32"}, "constructor": "New", "arguments": {"value": {"Expression": "1 + 1"}}}"#,
+        ),
+        (
+            "token_node",
+            r#"{"node_type": "flowrs_std::nodes::value::ValueNode", "type_parameters": {"I": "String"}, "constructor": "New", "arguments": {"value": {"Literal": "text"}}}"#,
+        ),
+    ];
+    for (node_name, node_json) in rejected_nodes {
+        let mut rejected_flow = flow_model.clone();
+        rejected_flow.nodes.insert(node_name.to_string(), serde_json::from_str(node_json).expect("wrong format."));
+        let err = NoStdCodeEmitter {}.emit_flow_code(&rejected_flow, &pm).expect_err("node not rejected.").to_string();
+        assert!(err.starts_with(&format!("Node '{}'", node_name)), "{}", err);
+    }
+
+    flow_model.data = serde_json::json!({});
+    assert!(NoStdCodeEmitter {}.emit_flow_code(&flow_model, &pm).is_err());
+
+    let pm = test_package_manager(false);
+    flow_model.data = serde_json::json!({"token_node": {"value": 42}});
+    assert!(NoStdCodeEmitter {}.emit_flow_code(&flow_model, &pm).is_err());
+}
+
+#[test]
+fn test_layout_round_trip() {
+    let flow_json = r##"
+    {
+        "nodes": {},
+        "connections": [],
+        "data": {},
+        "layout": {
+            "nodes": {"debug_node": {"color": "#ff0000", "collapsed": true}},
+            "notes": [{"id": "note_1", "text": "Hello", "position": {"x": 1.0, "y": 2.0}}],
+            "groups": [{"id": "group_1", "nodes": ["debug_node"]}],
+            "viewport": {"x": 0.0, "y": 0.0, "zoom": 1.5}
+        }
+    }
+    "##;
+
+    let flow_model: FlowModel = serde_json::from_str(flow_json).expect("wrong format.");
+    let flow_model: FlowModel = serde_json::from_str(&serde_json::to_string(&flow_model).unwrap()).expect("wrong format.");
+
+    let layout = flow_model.layout();
+    assert!(layout.nodes["debug_node"].collapsed);
+    assert_eq!(layout.notes[0].text, "Hello");
+    assert_eq!(layout.groups[0].nodes, vec!["debug_node".to_string()]);
+    assert_eq!(layout.viewport.as_ref().unwrap().zoom, 1.5);
+
+    let without_layout: FlowModel = serde_json::from_str(r#"{"nodes": {}, "connections": [], "data": {}}"#).expect("wrong format.");
+    assert!(without_layout.layout().notes.is_empty());
+}
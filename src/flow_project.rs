@@ -1,13 +1,16 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::flow_model::FlowModel;
+use crate::emitter_registry::{CodeEmitterFactory, EmitterInfo, EmitterRegistry, EmitterSelection};
+use crate::flow_model::{CodeEmitter, FlowLayout, FlowModel};
+use crate::package::Package;
 use crate::package_manager::PackageManager;
 
 use std::collections::HashMap;
 use std::fs;
 
 use std::io;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
 use serde_json;
@@ -15,21 +18,74 @@ use handlebars::Handlebars;
 
 use anyhow::Result;
 
-use crate::flow_model::{CodeEmitter, StandardCodeEmitter};
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct FlowPackage {
     name: String,
     version: String,
     path: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct FlowProject {
     name: String,
     version: String,
     packages: Vec<FlowPackage>,
     flow: FlowModel,
+    /// Emitter used to generate the code of the flow.
+    #[serde(default)]
+    emitter: EmitterSelection,
+}
+
+impl FlowProject {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn flow(&self) -> &FlowModel {
+        &self.flow
+    }
+
+    pub fn emitter(&self) -> &EmitterSelection {
+        &self.emitter
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum BuildTarget {
+    /// WebAssembly package built with wasm-pack.
+    Wasm,
+    /// Shared object for the desktop runner built with cargo.
+    Native,
+}
+
+/// Cargo arguments that build a generated crate for the browser only (passed after `--` to wasm-pack).
+/// Generated crates enable the `native` and `ffi` features by default.
+pub const WASM_CARGO_ARGS: [&str; 3] = ["--no-default-features", "--features", "wasm"];
+
+/// Name of the project description inside a project archive.
+const ARCHIVE_PROJECT_FILE_NAME: &str = "flow-project.json";
+
+/// Folder with the package descriptions inside a project archive.
+const ARCHIVE_PACKAGES_FOLDER: &str = "packages";
+
+/// Result of a project import.
+#[derive(Debug, Serialize, Clone)]
+pub struct FlowProjectImport {
+    pub project: FlowProject,
+    /// Differences between the archived packages and the packages of this instance
+    /// and problems generating the project (see `create_flow_project`).
+    pub warnings: Vec<String>,
+}
+
+/// Generated files of a project.
+#[derive(Debug, Serialize, Clone)]
+pub struct FlowProjectSources {
+    pub cargo_toml: String,
+    pub lib_rs: String,
+    /// None for emitters without a wasm entry point, e.g. no_std.
+    pub index_html: Option<String>,
+    /// Problems that did not prevent generation, e.g. failed formatting.
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -43,12 +99,24 @@ pub struct FlowProjectManagerConfig{
     
     #[serde(default = "builtin_dependencies_default")] 
     pub builtin_dependencies: Vec<String>,
+
+    /// Dependencies only compiled for wasm32 targets.
+    #[serde(default = "wasm_dependencies_default")]
+    pub wasm_dependencies: Vec<String>,
+
+    /// Dependencies only compiled for other targets than wasm32.
+    #[serde(default)]
+    pub native_dependencies: Vec<String>,
     
     #[serde(default = "rust_fmt_path_default")] 
     pub rust_fmt_path: String,
 
     #[serde(default = "do_formatting_default")] 
-    pub do_formatting: bool
+    pub do_formatting: bool,
+
+    /// Format with the rustfmt binary at `rust_fmt_path` instead of the built-in formatter.
+    #[serde(default)]
+    pub use_rust_fmt: bool
 }
 
 impl Default for FlowProjectManagerConfig {
@@ -57,8 +125,11 @@ impl Default for FlowProjectManagerConfig {
             project_folder: project_folder_default(),
             project_json_file_name: project_json_file_name_default(),
             builtin_dependencies: builtin_dependencies_default(),
+            wasm_dependencies: wasm_dependencies_default(),
+            native_dependencies: Vec::new(),
             rust_fmt_path: rust_fmt_path_default(),
-            do_formatting: do_formatting_default()
+            do_formatting: do_formatting_default(),
+            use_rust_fmt: false
         }
     }
 }
@@ -73,7 +144,40 @@ fn project_json_file_name_default() -> String {
 }
 
 fn builtin_dependencies_default() -> Vec<String> {
-    vec!["wasm-bindgen = \"0.2.87\"".to_string(), "serde_json = \"1.0.105\"".to_string()]
+    vec!["serde_json = \"1.0.105\"".to_string()]
+}
+
+fn wasm_dependencies_default() -> Vec<String> {
+    vec!["wasm-bindgen = \"0.2.87\"".to_string(), "js-sys = \"0.3.64\"".to_string()]
+}
+
+fn get_dependency_name(dependency: &str) -> &str {
+    dependency.split('=').next().unwrap_or_default().trim()
+}
+
+/// Marks dependencies like `name = "1.0"` or `name = {path = "..."}` as optional.
+fn create_optional_dependencies(dependencies: &[String]) -> String {
+    dependencies
+        .iter()
+        .map(|dependency| {
+            let name = get_dependency_name(dependency);
+            let spec = dependency.split_once('=').map_or("", |(_, spec)| spec.trim());
+            match spec.strip_prefix('{') {
+                Some(fields) => format!("{} = {{optional = true, {}", name, fields.trim_start()),
+                None => format!("{} = {{version = {}, optional = true}}", name, spec),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Feature entries enabling optional dependencies, e.g. `"dep:js-sys"`.
+fn create_dependency_features(dependencies: &[String]) -> String {
+    dependencies
+        .iter()
+        .map(|dependency| format!("\"dep:{}\"", get_dependency_name(dependency)))
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
 fn rust_fmt_path_default() -> String {
@@ -88,16 +192,28 @@ const fn do_formatting_default() -> bool {
 pub struct FlowProjectManager {
     config: FlowProjectManagerConfig,
     pub projects: HashMap<String, FlowProject>,
+    #[serde(skip)]
+    emitters: EmitterRegistry,
 }
 
 impl FlowProjectManager {
     pub fn new(config: FlowProjectManagerConfig) -> Self {
         Self {
-            config: config,
+            config,
             projects: HashMap::new(),
+            emitters: EmitterRegistry::new(),
         }
     }
 
+    /// Makes an additional emitter available to projects. An emitter with the same name is replaced.
+    pub fn register_emitter(&mut self, name: &str, description: &str, factory: CodeEmitterFactory) {
+        self.emitters.register(name, description, factory);
+    }
+
+    pub fn get_emitter_infos(&self) -> Vec<EmitterInfo> {
+        self.emitters.get_emitter_infos()
+    }
+
     pub fn load_projects(&mut self) -> Result<()> {
         self.projects.clear();
         for entry in fs::read_dir(&self.config.project_folder)? {
@@ -115,21 +231,29 @@ impl FlowProjectManager {
         Ok(())
     }
 
+    /// Creates the project folder with the generated code. Returns the project and warnings,
+    /// e.g. that the generated code could not be formatted.
     pub fn create_flow_project(
         &mut self,
         flow_project: FlowProject,
         package_manager: &PackageManager,
-    ) -> Result<FlowProject, anyhow::Error> {
+    ) -> Result<(FlowProject, Vec<String>), anyhow::Error> {
         if self.projects.contains_key(&flow_project.name) {
-            return Ok(flow_project);
+            return Ok((flow_project, Vec::new()));
         }
 
+        // Check the flow data before anything is generated, so bad data does not panic in init().
+        check_flow_data(&flow_project, package_manager)?;
+
+        // Unknown emitters and invalid emitter options are rejected before the project folder exists.
+        self.emitters.create_emitter(&flow_project.emitter)?;
+
         self.projects
             .insert(flow_project.name.clone(), flow_project.clone());
 
-        self.create_flow_project_folder(&flow_project, package_manager)?;
+        let warnings = self.create_flow_project_folder(&flow_project, package_manager)?;
 
-        Ok(flow_project)
+        Ok((flow_project, warnings))
     }
 
     fn create_project_dependencies(&self, p: &FlowPackage) -> String {
@@ -140,30 +264,75 @@ impl FlowProjectManager {
         }
     }
 
-    fn create_builtin_dependencies(&self) -> String {
-        self.config.builtin_dependencies.join("\n")
+    /// The builtin dependencies on the crates the emitted code uses.
+    fn create_builtin_dependencies(&self, dependencies: &[String]) -> String {
+        self.config
+            .builtin_dependencies
+            .iter()
+            .filter(|dependency| {
+                let crate_name = get_dependency_name(dependency);
+                dependencies.iter().any(|d| d == crate_name)
+            })
+            .cloned()
+            .collect::<Vec<String>>()
+            .join("\n")
     }
 
-    fn create_cargo_toml(
+    /// Features of generated crates: `wasm` for the browser entry point, `native` for running the flow
+    /// from Rust on the desktop and `ffi` for the C interface used by the desktop runner.
+    /// `wasm` and `native` enable their (optional) target dependencies.
+    fn create_features(&self) -> String {
+        format!(
+            "default = [\"native\", \"ffi\"]\nwasm = [{}]\nnative = [{}]\nffi = [\"native\"]",
+            create_dependency_features(&self.config.wasm_dependencies),
+            create_dependency_features(&self.config.native_dependencies)
+        )
+    }
+
+    fn create_cargo_toml_content(
         &self,
         flow_project: &FlowProject,
-        project_folder_name: &PathBuf,
-    ) -> Result<()> {
-        
-        let content =
-            format!("[package]\n name = \"{}\" \n version = \"{}\"\nedition = \"2021\"\n\n[dependencies]\n{}\n{}\n\n[lib]\ncrate-type = [\"cdylib\"]", 
+        package_manager: &PackageManager,
+        emitter: &dyn CodeEmitter,
+    ) -> Result<String, anyhow::Error> {
+        if emitter.is_no_std() {
+            // A plain library for the firmware crate, builtin dependencies would pull in std.
+            return Ok(format!("[package]\nname = \"{}\"\nversion = \"{}\"\nedition = \"2021\"\n\n[dependencies]\n{}\n",
+                flow_project.name,
+                flow_project.version,
+                flow_project.packages.iter().map(|x| self.create_project_dependencies(x)).collect::<Vec<String>>().join("\n"),
+            ));
+        }
+
+        let dependencies = emitter.get_dependencies(&flow_project.flow, package_manager)?;
+
+        Ok(format!("[package]\n name = \"{}\" \n version = \"{}\"\nedition = \"2021\"\n\n[dependencies]\n{}\n{}\n\n[target.'cfg(target_arch = \"wasm32\")'.dependencies]\n{}\n\n[target.'cfg(not(target_arch = \"wasm32\"))'.dependencies]\n{}\n\n[features]\n{}\n\n[lib]\ncrate-type = [\"cdylib\"]", 
             flow_project.name,
             flow_project.version,
             flow_project.packages.iter().map(|x| self.create_project_dependencies(x)).collect::<Vec<String>>().join("\n"),
-            self.create_builtin_dependencies()
-        );
+            self.create_builtin_dependencies(&dependencies),
+            create_optional_dependencies(&self.config.wasm_dependencies),
+            create_optional_dependencies(&self.config.native_dependencies),
+            self.create_features()
+        ))
+    }
+
+    fn create_cargo_toml(
+        &self,
+        flow_project: &FlowProject,
+        project_folder_name: &Path,
+        package_manager: &PackageManager,
+        emitter: &dyn CodeEmitter,
+    ) -> Result<()> {
+        
+        let content = self.create_cargo_toml_content(flow_project, package_manager, emitter)?;
 
         self.create_project_file(project_folder_name, &"Cargo.toml".to_string(), &content)
     }
 
     fn create_project_file(
         &self,        
-        folder_name: &PathBuf,
+        folder_name: &Path,
         file_name: &String, 
         content: &String
     ) -> Result<(), anyhow::Error> {
@@ -178,19 +347,19 @@ impl FlowProjectManager {
     fn create_flow_proj_json(
         &self,
         flow_project: &FlowProject,
-        project_folder_name: &PathBuf,
+        project_folder_name: &Path,
     ) -> Result<(), anyhow::Error> {
         
         let content = serde_json::to_string(&flow_project)?;
         self.create_project_file(project_folder_name, &self.config.project_json_file_name, &content)
     }
 
-    fn create_index_html(
-        &self,
-        flow_project: &FlowProject,
-        project_folder_name: &PathBuf,
-    ) -> Result<(), anyhow::Error> {
-        
+    /// Page running the flow through its wasm `FlowHandle`. None if the emitted code has no wasm entry point.
+    fn create_index_html_content(&self, flow_project: &FlowProject, emitter: &dyn CodeEmitter) -> Result<Option<String>, anyhow::Error> {
+        if emitter.is_no_std() {
+            return Ok(Option::None);
+        }
+
         let mut handlebars = Handlebars::new();
         let source = r#"
         <!DOCTYPE html>
@@ -201,13 +370,28 @@ impl FlowProjectManager {
           </head>
           <body>
             <script type="module">
-              import init, {wasm_run} from '/pkg/{{project_name}}.js'
+              import init, {FlowHandle} from '/pkg/{{project_name}}.js'
         
               // Always required for wasm.
               await init();
 
-              // Running flow.
-              wasm_run();
+              // Running flow, a few milliseconds per animation frame. Errors are thrown as exceptions.
+              try {
+                const flow = FlowHandle.init();
+
+                const frame = () => {
+                  try {
+                    flow.run_for(8);
+                    requestAnimationFrame(frame);
+                  } catch (err) {
+                    console.error("Flow failed: " + err);
+                    flow.cancel();
+                  }
+                };
+                requestAnimationFrame(frame);
+              } catch (err) {
+                console.error("Flow failed: " + err);
+              }
               
             </script>
           </body>
@@ -220,49 +404,119 @@ impl FlowProjectManager {
         data.insert("project_name", &flow_project.name);
         data.insert("project_version", &flow_project.version);
 
-        let content = handlebars.render("index", &data)?;
+        Ok(Some(handlebars.render("index", &data)?))
+    }
 
-        self.create_project_file(project_folder_name, &"index.html".to_string(), &content)
+    fn create_index_html(
+        &self,
+        flow_project: &FlowProject,
+        project_folder_name: &Path,
+        emitter: &dyn CodeEmitter,
+    ) -> Result<(), anyhow::Error> {
+        
+        match self.create_index_html_content(flow_project, emitter)? {
+            Some(content) => self.create_project_file(project_folder_name, &"index.html".to_string(), &content),
+            None => Ok(()),
+        }
     }
 
     fn create_flow_rust_code(
         &self,
         flow_project: &FlowProject,
-        src_folder: &PathBuf,
+        src_folder: &Path,
         package_manager: &PackageManager,
-    ) -> Result<(), anyhow::Error> {
+        emitter: &dyn CodeEmitter,
+    ) -> Result<Vec<String>, anyhow::Error> {
 
-        let emitter = StandardCodeEmitter {};
-        let content = &emitter.emit_flow_code(&flow_project.flow, package_manager)?;
+        let mut warnings = Vec::new();
+        let mut content = emitter.emit_flow_code(&flow_project.flow, package_manager)?;
 
-        self.create_project_file(src_folder, &"lib.rs".to_string(), &content)?;
         if self.config.do_formatting {
-            self.run_rust_fmt(&src_folder.join("lib.rs"));
+            content = self.format_generated_code(content, &mut warnings);
         }
-        Ok(())
+
+        self.create_project_file(src_folder, &"lib.rs".to_string(), &content)?;
+        Ok(warnings)
     }
 
-    fn run_rust_fmt(&self, file_path: &PathBuf) {
-        let mut command = std::process::Command::new(&self.config.rust_fmt_path);
-        command.arg(file_path.to_str().unwrap());
+    /// Returns the formatted code or, if formatting fails, the code as is and a warning.
+    fn format_generated_code(&self, code: String, warnings: &mut Vec<String>) -> String {
+        match self.format_rust_code(&code) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                warnings.push(format!("Could not format generated code: {}", err));
+                code
+            }
+        }
+    }
 
-        let status = command.output().unwrap();
+    /// Formats generated code in-process. External rustfmt is only used if `use_rust_fmt` is set.
+    fn format_rust_code(&self, code: &str) -> Result<String, anyhow::Error> {
+        if self.config.use_rust_fmt {
+            return self.run_rust_fmt(code);
+        }
+
+        let file = syn::parse_file(code)?;
+        Ok(prettyplease::unparse(&file))
+    }
+
+    /// Generates all project files in memory without touching the project folder.
+    /// Fails like create_flow_project if the flow data is invalid or code generation fails.
+    pub fn generate_flow_project_sources(
+        &self,
+        flow_project: &FlowProject,
+        package_manager: &PackageManager,
+        do_formatting: bool,
+    ) -> Result<FlowProjectSources, anyhow::Error> {
+        check_flow_data(flow_project, package_manager)?;
+
+        let emitter = self.emitters.create_emitter(&flow_project.emitter)?;
+        let mut lib_rs = emitter.emit_flow_code(&flow_project.flow, package_manager)?;
+        let mut warnings = Vec::new();
 
-        //TODO: better error reporting. also: make fmt optional and add the possibility to change its path.
-        if status.status.code() != Some(0) {
-            println!(
-                "An error occurred while formatting {}: {}",
-                file_path.to_string_lossy(),
-                String::from_utf8(status.stderr).expect("")
-            );
+        if do_formatting {
+            lib_rs = self.format_generated_code(lib_rs, &mut warnings);
         }
+
+        Ok(FlowProjectSources {
+            cargo_toml: self.create_cargo_toml_content(flow_project, package_manager, emitter.as_ref())?,
+            lib_rs,
+            index_html: self.create_index_html_content(flow_project, emitter.as_ref())?,
+            warnings,
+        })
+    }
+
+    /// Formats code with the external rustfmt via stdin.
+    fn run_rust_fmt(&self, code: &str) -> Result<String, anyhow::Error> {
+        let mut child = std::process::Command::new(&self.config.rust_fmt_path)
+            .args(["--edition", "2021"])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|err| anyhow::Error::msg(format!("Could not run '{}': {}", self.config.rust_fmt_path, err)))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(code.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+
+        if !output.status.success() {
+            return Err(anyhow::Error::msg(format!(
+                "'{}' failed: {}",
+                self.config.rust_fmt_path,
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 
     fn create_flow_project_folder(
         &self,
         flow_project: &FlowProject,
         package_manager: &PackageManager,
-    ) -> Result<(), anyhow::Error> {
+    ) -> Result<Vec<String>, anyhow::Error> {
 
         // Create the main project folder using the FlowProject's name
         let project_folder_name = Path::new(&self.config.project_folder).join(&flow_project.name);
@@ -274,13 +528,13 @@ impl FlowProjectManager {
 
         self.create_flow_proj_json(flow_project, &project_folder_name)?;
 
-        self.create_cargo_toml(flow_project, &project_folder_name)?;
+        let emitter = self.emitters.create_emitter(&flow_project.emitter)?;
 
-        self.create_index_html(flow_project, &project_folder_name)?;
+        self.create_cargo_toml(flow_project, &project_folder_name, package_manager, emitter.as_ref())?;
 
-        self.create_flow_rust_code(flow_project, &src_folder, package_manager)?;
+        self.create_index_html(flow_project, &project_folder_name, emitter.as_ref())?;
 
-        Ok(())
+        self.create_flow_rust_code(flow_project, &src_folder, package_manager, emitter.as_ref())
     }
 
     pub fn delete_flow_project(&mut self, name: &str) -> Result<(), anyhow::Error>  {
@@ -297,15 +551,121 @@ impl FlowProjectManager {
         Ok(())
     }
 
+    pub fn get_project_folder(&self, name: &str) -> PathBuf {
+        Path::new(&self.config.project_folder).join(name)
+    }
+
+    /// Builds a generated project and returns the path of the build artifact
+    /// (the .wasm file for Wasm, the shared object for Native).
+    pub fn build_flow_project(&self, name: &str, target: BuildTarget, release: bool) -> Result<PathBuf, anyhow::Error> {
+        if !self.projects.contains_key(name) {
+            return Err(anyhow::Error::msg(format!("Project '{}' does not exist.", name)));
+        }
+
+        build_project_folder(&self.get_project_folder(name), name, target, release)
+    }
+
+    /// Packs the project description and the descriptions of all its packages known to the package manager
+    /// into a tar archive. Returns None if the project does not exist.
+    pub fn export_flow_project(&self, name: &str, package_manager: &PackageManager) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        let flow_project = match self.projects.get(name) {
+            Some(flow_project) => flow_project,
+            None => return Ok(Option::None),
+        };
+
+        let mut builder = tar::Builder::new(Vec::new());
+        append_archive_file(&mut builder, ARCHIVE_PROJECT_FILE_NAME, &serde_json::to_string_pretty(flow_project)?)?;
+
+        for p in &flow_project.packages {
+            if let Some(package) = package_manager.get_package(&p.name) {
+                append_archive_file(
+                    &mut builder,
+                    &format!("{}/{}.json", ARCHIVE_PACKAGES_FOLDER, package.name),
+                    &serde_json::to_string_pretty(package)?,
+                )?;
+            }
+        }
+
+        Ok(Some(builder.into_inner()?))
+    }
+
+    /// Recreates a project from an archive created by export_flow_project.
+    /// Packages are not installed. Archived packages that are missing or different on this instance are reported as warnings.
+    pub fn import_flow_project(&mut self, archive: &[u8], package_manager: &PackageManager) -> Result<FlowProjectImport, anyhow::Error> {
+        let mut flow_project: Option<FlowProject> = Option::None;
+        let mut packages: Vec<Package> = Vec::new();
+
+        for entry in tar::Archive::new(archive).entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_path_buf();
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+
+            if path == Path::new(ARCHIVE_PROJECT_FILE_NAME) {
+                flow_project = Some(serde_json::from_str(&content).map_err(|err| {
+                    anyhow::Error::msg(format!("Could not read project description from archive: {}", err))
+                })?);
+            } else if path.starts_with(ARCHIVE_PACKAGES_FOLDER) {
+                packages.push(serde_json::from_str(&content).map_err(|err| {
+                    anyhow::Error::msg(format!("Could not read package '{}' from archive: {}", path.to_string_lossy(), err))
+                })?);
+            }
+        }
+
+        let flow_project = flow_project.ok_or_else(|| {
+            anyhow::Error::msg(format!("Archive does not contain '{}'.", ARCHIVE_PROJECT_FILE_NAME))
+        })?;
+
+        if self.projects.contains_key(&flow_project.name) {
+            return Err(anyhow::Error::msg(format!("Project '{}' already exists.", flow_project.name)));
+        }
+
+        let mut warnings = Vec::new();
+        for package in &packages {
+            match package_manager.get_package(&package.name) {
+                None => warnings.push(format!("Package '{}' is not available.", package.name)),
+                Some(existing) if existing.version != package.version => warnings.push(format!(
+                    "Package '{}' has version {} instead of {}.",
+                    package.name, existing.version, package.version
+                )),
+                Some(existing) if serde_json::to_value(existing)? != serde_json::to_value(package)? => warnings.push(format!(
+                    "Package '{}' differs from the exported description.",
+                    package.name
+                )),
+                _ => {}
+            }
+        }
+
+        let (project, creation_warnings) = self.create_flow_project(flow_project, package_manager)?;
+        warnings.extend(creation_warnings);
+
+        Ok(FlowProjectImport { project, warnings })
+    }
+
+    /// Replaces the editor layout of a project's flow and writes the project file.
+    /// The generated code does not depend on the layout, so it is not touched.
+    /// The project file is written first, so the project in memory keeps its previous layout if writing fails.
+    pub fn update_flow_project_layout(&mut self, name: &str, layout: FlowLayout) -> Result<Option<FlowProject>, anyhow::Error> {
+        let mut fp = match self.projects.get(name) {
+            Some(fp) => fp.clone(),
+            None => return Ok(None),
+        };
+        fp.flow.set_layout(layout);
+
+        self.write_flow_project_json(&fp)?;
+        self.projects.insert(name.to_string(), fp.clone());
+
+        Ok(Some(fp))
+    }
+
     pub fn update_flow_project_flow_model(&mut self, name: &str, flow: FlowModel) -> Result<(), anyhow::Error> {
-        if let Some(fp) = self.projects.get_mut(name) {
+        if let Some(fp) = self.projects.get(name) {
+            let mut fp = fp.clone();
             fp.flow = flow;
 
             // Write project file.
-            let project_folder_name = Path::new(&self.config.project_folder).join(&fp.name);
-            let flow_model_json_content = serde_json::to_string(&fp)?;
-            let flow_project_json_path = project_folder_name.join(&self.config.project_json_file_name);
-            replace_file_contents(&flow_project_json_path, &flow_model_json_content)?;
+            self.write_flow_project_json(&fp)?;
+            self.projects.insert(name.to_string(), fp);
 
             // Update Cargo.toml TODO
 
@@ -314,6 +674,79 @@ impl FlowProjectManager {
 
         Ok(())
     }
+
+    fn write_flow_project_json(&self, fp: &FlowProject) -> Result<(), anyhow::Error> {
+        let project_folder_name = Path::new(&self.config.project_folder).join(&fp.name);
+        let flow_project_json_content = serde_json::to_string(fp)?;
+        let flow_project_json_path = project_folder_name.join(&self.config.project_json_file_name);
+        replace_file_contents(&flow_project_json_path, &flow_project_json_content)?;
+        Ok(())
+    }
+}
+
+/// Builds the generated project in `project_folder` (see `build_flow_project`). Does not need the
+/// `FlowProjectManager`, so callers sharing it can release it during the build.
+pub fn build_project_folder(project_folder: &Path, name: &str, target: BuildTarget, release: bool) -> Result<PathBuf, anyhow::Error> {
+    let crate_name = name.replace('-', "_");
+    let profile = if release { "release" } else { "debug" };
+
+    let mut command = match target {
+        BuildTarget::Wasm => {
+            let mut command = std::process::Command::new("wasm-pack");
+            command.args(["build", "--target", "web"]);
+            command.arg(if release { "--release" } else { "--dev" });
+            command.arg("--").args(WASM_CARGO_ARGS);
+            command
+        }
+        BuildTarget::Native => {
+            let mut command = std::process::Command::new("cargo");
+            command.arg("build");
+            if release {
+                command.arg("--release");
+            }
+            command
+        }
+    };
+
+    let output = command
+        .current_dir(project_folder)
+        .output()
+        .map_err(|err| anyhow::Error::msg(format!("Could not start build of project '{}': {}", name, err)))?;
+    if !output.status.success() {
+        return Err(anyhow::Error::msg(format!(
+            "Build of project '{}' failed:\n{}",
+            name,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let artifact = match target {
+        BuildTarget::Wasm => project_folder.join("pkg").join(format!("{}_bg.wasm", crate_name)),
+        BuildTarget::Native => project_folder.join("target").join(profile).join(format!(
+            "{}{}{}",
+            std::env::consts::DLL_PREFIX,
+            crate_name,
+            std::env::consts::DLL_SUFFIX
+        )),
+    };
+
+    if !artifact.exists() {
+        return Err(anyhow::Error::msg(format!("Build artifact '{}' cannot be found.", artifact.to_string_lossy())));
+    }
+
+    Ok(artifact)
+}
+
+fn check_flow_data(flow_project: &FlowProject, package_manager: &PackageManager) -> Result<(), anyhow::Error> {
+    let mut issues = flow_project.flow.validate_data(package_manager);
+    issues.extend(flow_project.flow.validate_ports(package_manager));
+    if !issues.is_empty() {
+        return Err(anyhow::Error::msg(format!(
+            "Invalid flow:\n{}",
+            issues.iter().map(|issue| issue.to_string()).collect::<Vec<String>>().join("\n")
+        )));
+    }
+    Ok(())
 }
 
 fn delete_folder_recursive(folder_path: &Path) -> io::Result<()> {
@@ -332,8 +765,190 @@ fn delete_folder_recursive(folder_path: &Path) -> io::Result<()> {
     Ok(())
 }
 
+fn append_archive_file(builder: &mut tar::Builder<Vec<u8>>, path: &str, content: &str) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, path, content.as_bytes())
+}
+
+/// Writes to a temporary file and renames it, so the file is never left half-written.
 fn replace_file_contents(file_path: &Path, new_content: &str) -> io::Result<()> {
-    let mut file = fs::File::create(file_path)?;
-    file.write_all(new_content.as_bytes())?;
-    Ok(())
+    let tmp_file_path = file_path.with_extension("tmp");
+    fs::write(&tmp_file_path, new_content)?;
+    fs::rename(&tmp_file_path, file_path)
+}
+
+#[test]
+fn test_export_and_import_project() {
+    let folder = std::env::temp_dir().join("flowrs_build_test_export_and_import_project");
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(folder.join("source")).expect("cannot create test folder.");
+    fs::create_dir_all(folder.join("target")).expect("cannot create test folder.");
+
+    let config = |name: &str| FlowProjectManagerConfig {
+        project_folder: folder.join(name).to_string_lossy().to_string(),
+        do_formatting: false,
+        ..Default::default()
+    };
+
+    let mut pm = PackageManager::new();
+    pm.add_package(serde_json::from_str(include_str!("../flow-packages/flowrs-std.json")).expect("wrong format."));
+
+    let flow_project: FlowProject = serde_json::from_str(
+        r#"
+    {
+        "name": "archived_project",
+        "version": "1.0.0",
+        "packages": [{"name": "flowrs-std", "version": "1.0.0"}],
+        "flow": {"nodes": {}, "connections": [], "data": {}}
+    }
+    "#,
+    )
+    .expect("wrong format.");
+
+    let mut source = FlowProjectManager::new(config("source"));
+    source.create_flow_project(flow_project, &pm).expect("cannot create project.");
+    let archive = source
+        .export_flow_project("archived_project", &pm)
+        .expect("cannot export project.")
+        .expect("project not found.");
+    assert!(source.export_flow_project("unknown", &pm).unwrap().is_none());
+
+    let mut target = FlowProjectManager::new(config("target"));
+    let import = target.import_flow_project(&archive, &PackageManager::new()).expect("cannot import project.");
+    assert_eq!(import.project.name(), "archived_project");
+    assert_eq!(import.warnings, vec!["Package 'flowrs-std' is not available.".to_string()]);
+    assert!(folder.join("target").join("archived_project").join("src").join("lib.rs").exists());
+
+    assert!(target.import_flow_project(&archive, &pm).is_err());
+
+    let _ = fs::remove_dir_all(&folder);
+}
+
+#[test]
+fn test_generate_flow_project_sources() {
+    let folder = std::env::temp_dir().join("flowrs_build_test_generate_flow_project_sources");
+    let _ = fs::remove_dir_all(&folder);
+
+    let project_manager = FlowProjectManager::new(FlowProjectManagerConfig {
+        project_folder: folder.to_string_lossy().to_string(),
+        ..Default::default()
+    });
+
+    let mut pm = PackageManager::new();
+    pm.add_package(serde_json::from_str(include_str!("../flow-packages/flowrs-std.json")).expect("wrong format."));
+
+    let flow_project: FlowProject = serde_json::from_str(
+        r#"
+    {
+        "name": "preview_project",
+        "version": "1.0.0",
+        "packages": [{"name": "flowrs-std", "version": "1.0.0"}],
+        "flow": {
+            "nodes": {
+                "debug_node": {
+                    "node_type": "flowrs_std::nodes::debug::DebugNode",
+                    "type_parameters": {"I": "i32"},
+                    "constructor": "New"
+                }
+            },
+            "connections": [],
+            "data": {}
+        }
+    }
+    "#,
+    )
+    .expect("wrong format.");
+
+    let sources = project_manager
+        .generate_flow_project_sources(&flow_project, &pm, true)
+        .expect("cannot generate sources.");
+
+    assert!(sources.cargo_toml.contains("name = \"preview_project\""));
+    assert!(sources.cargo_toml.contains("flowrs-std = \"1.0.0\""));
+    assert!(sources.cargo_toml.contains("[target.'cfg(target_arch = \"wasm32\")'.dependencies]\nwasm-bindgen = {version = \"0.2.87\", optional = true}"));
+    assert!(sources.cargo_toml.contains("wasm = [\"dep:wasm-bindgen\", \"dep:js-sys\"]"));
+    assert!(sources.cargo_toml.contains("native = []"));
+    assert!(sources.cargo_toml.contains("ffi = [\"native\"]"));
+    assert_eq!(
+        create_optional_dependencies(&["local = {path = \"../local\"}".to_string()]),
+        "local = {optional = true, path = \"../local\"}"
+    );
+    assert!(sources.lib_rs.contains("DebugNode"));
+    assert!(sources.lib_rs.contains("Result<ExecutionContext, InitError>"));
+    assert!(sources.warnings.is_empty());
+    assert!(sources.index_html.expect("index.html missing.").contains("/pkg/preview_project.js"));
+    assert!(sources.cargo_toml.contains("serde_json = "));
+    assert!(!folder.exists());
+
+    // Without data parsed at runtime or ports passed as JSON, the generated code does not use serde_json.
+    let mut embedded_project = serde_json::to_value(&flow_project).unwrap();
+    embedded_project["emitter"] = serde_json::json!({"name": "standard", "options": {"embed_data": true}});
+    let embedded_project: FlowProject = serde_json::from_value(embedded_project).expect("wrong format.");
+    let sources = project_manager
+        .generate_flow_project_sources(&embedded_project, &pm, false)
+        .expect("cannot generate sources.");
+    assert!(!sources.cargo_toml.contains("serde_json"));
+    assert!(!sources.lib_rs.contains("serde_json"));
+
+    // no_std crates are plain libraries without the wasm, native and ffi glue.
+    let no_std_emitter = crate::flow_model::NoStdCodeEmitter {};
+    let cargo_toml = project_manager.create_cargo_toml_content(&flow_project, &pm, &no_std_emitter).expect("cannot create Cargo.toml.");
+    assert!(cargo_toml.contains("flowrs-std = \"1.0.0\""));
+    for glue in ["cdylib", "[features]", "wasm-bindgen", "serde_json"] {
+        assert!(!cargo_toml.contains(glue));
+    }
+    assert!(project_manager.create_index_html_content(&flow_project, &no_std_emitter).expect("cannot render index.html.").is_none());
+
+    // Formatting problems are returned as warnings and the unformatted code is written.
+    let mut project_manager = FlowProjectManager::new(FlowProjectManagerConfig {
+        project_folder: folder.to_string_lossy().to_string(),
+        use_rust_fmt: true,
+        rust_fmt_path: folder.join("missing-rustfmt").to_string_lossy().to_string(),
+        ..Default::default()
+    });
+    fs::create_dir_all(&folder).expect("cannot create test folder.");
+    let (_, warnings) = project_manager.create_flow_project(flow_project, &pm).expect("cannot create project.");
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].starts_with("Could not format generated code"));
+    assert!(folder.join("preview_project").join("src").join("lib.rs").exists());
+
+    let _ = fs::remove_dir_all(&folder);
+}
+
+#[test]
+fn test_update_flow_project_layout() {
+    let folder = std::env::temp_dir().join("flowrs_build_test_update_flow_project_layout");
+    let _ = fs::remove_dir_all(&folder);
+
+    let mut project_manager = FlowProjectManager::new(FlowProjectManagerConfig {
+        project_folder: folder.to_string_lossy().to_string(),
+        ..Default::default()
+    });
+    let flow_project: FlowProject = serde_json::from_str(
+        r#"{"name": "layout_project", "version": "1.0.0", "packages": [], "flow": {"nodes": {}, "connections": [], "data": {}}}"#,
+    )
+    .expect("wrong format.");
+    project_manager.projects.insert(flow_project.name.clone(), flow_project);
+
+    let layout: FlowLayout = serde_json::from_str(r#"{"notes": [{"id": "n1", "text": "Hello", "position": {"x": 0.0, "y": 0.0}}]}"#).expect("wrong format.");
+
+    // The project folder does not exist, so writing fails and the layout stays as it was.
+    assert!(project_manager.update_flow_project_layout("layout_project", layout.clone()).is_err());
+    assert!(project_manager.projects["layout_project"].flow().layout().notes.is_empty());
+
+    fs::create_dir_all(folder.join("layout_project")).expect("cannot create test folder.");
+    let updated = project_manager
+        .update_flow_project_layout("layout_project", layout)
+        .expect("cannot update layout.")
+        .expect("project not found.");
+    assert_eq!(updated.flow().layout().notes.len(), 1);
+    assert_eq!(project_manager.projects["layout_project"].flow().layout().notes.len(), 1);
+    assert!(fs::read_to_string(folder.join("layout_project").join("flow-project.json")).unwrap().contains("Hello"));
+
+    assert!(project_manager.update_flow_project_layout("unknown", FlowLayout::default()).unwrap().is_none());
+
+    let _ = fs::remove_dir_all(&folder);
 }
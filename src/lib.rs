@@ -1,4 +1,10 @@
+pub mod data_literal;
+pub mod emitter_registry;
+pub mod flow_diff;
 pub mod flow_model;
 pub mod flow_project;
 pub mod package;
 pub mod package_manager;
+pub mod runner;
+pub mod schema;
+pub mod service_config;
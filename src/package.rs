@@ -1,18 +1,28 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use anyhow::{Error, Result};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::parse_quote;
+use serde_json::Value;
+use std::cell::RefCell;
 use std::collections::HashMap;
 
+use crate::data_literal::{DataLiteralEmitter, UnsupportedType};
 use crate::package_manager::PackageManager;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Package {
     pub name: String,
     pub version: String,
     pub crates: HashMap<String, Crate>,
+    /// The crates of this package can be used in `#![no_std]` flows (see the `no_std` emitter).
+    #[serde(default)]
+    pub no_std: bool,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Crate {
     pub types: HashMap<String, Type>,
     pub modules: HashMap<String, Module>,
@@ -24,24 +34,38 @@ pub struct Crate {
 impl Crate {
     pub fn new_with_types(types: HashMap<String, Type>) -> Self {
         Self {
-            types: types,
+            types,
             modules: HashMap::new(),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Module {
     pub types: HashMap<String, Type>,
     pub modules: HashMap<String, Module>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Type {
     pub inputs: Option<Vec<String>>,
     pub outputs: Option<Vec<String>>,
     pub type_parameters: Option<Vec<String>>,
     pub constructors: HashMap<String, Constructor>,
+    pub documentation: Option<String>,
+    /// JSON schema of the flow data this type is built from by a `FromJson` constructor.
+    pub data_schema: Option<Value>,
+    /// Public fields of a struct type. Allows flow data of this type to be emitted as a struct literal.
+    pub fields: Option<Vec<Field>>,
+}
+
+/// Public field of a struct type, e.g. `{"name": "secs", "type": "u64"}`.
+/// The type can use the type parameters of the struct.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct Field {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
 }
 
 impl Type {
@@ -50,7 +74,10 @@ impl Type {
             inputs: Option::None,
             outputs: Option::None,
             type_parameters: Option::None,
-            constructors: HashMap::new()
+            constructors: HashMap::new(),
+            documentation: Option::None,
+            data_schema: Option::None,
+            fields: Option::None,
         };
         t.constructors.insert(constructor_name.into(), constructor);
         t
@@ -61,7 +88,10 @@ impl Type {
             inputs: Option::None,
             outputs: Option::None,
             type_parameters: Option::None,
-            constructors: HashMap::new()
+            constructors: HashMap::new(),
+            documentation: Option::None,
+            data_schema: Option::None,
+            fields: Option::None,
         };
         t.constructors.insert("Default".into(), Constructor::FromDefault);
         t.constructors.insert("Json".into(), Constructor::FromJson);
@@ -74,12 +104,43 @@ impl Type {
             inputs: Option::None,
             outputs: Option::None,
             type_parameters: Option::None,
-            constructors: HashMap::new()
+            constructors: HashMap::new(),
+            documentation: Option::None,
+            data_schema: Option::None,
+            fields: Option::None,
         }
     }
 
 }
 
+/// Location in the flow data an object is deserialized from by a `FromJson` constructor.
+pub struct JsonDataBinding {
+    pub path: Vec<String>,
+    pub type_name: String,
+}
+
+/// Where objects built by a `FromJson` constructor get their value from.
+pub enum JsonDataSource<'a> {
+    /// Deserialized at runtime from the `data` local of the generated init function.
+    Runtime,
+    /// Converted at build time into a Rust expression from the given flow data.
+    /// Vec, String and Box values are only emitted if `allow_alloc` is set.
+    Literal { data: &'a Value, allow_alloc: bool },
+    /// Converted at build time where possible, deserialized at runtime otherwise.
+    /// The data paths of all objects deserialized at runtime are collected in `fallbacks`.
+    Embedded { data: &'a Value, fallbacks: RefCell<Vec<Vec<String>>> },
+}
+
+impl<'a> JsonDataSource<'a> {
+    /// Whether emitted values may allocate (String, Vec, Box).
+    pub fn allows_alloc(&self) -> bool {
+        match self {
+            Self::Literal { allow_alloc, .. } => *allow_alloc,
+            _ => true,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Namespace {
     parts: Vec<String>,
@@ -90,8 +151,11 @@ impl Namespace {
         Self { parts: Vec::new() }
     }
 
-    fn add_part(&mut self, part: &str) {
+    /// Parts become prefixes of local names, so they have to be identifiers.
+    fn add_part(&mut self, part: &str) -> Result<(), Error> {
+        parse_code::<Ident>(part, "object name")?;
         self.parts.push(part.to_string());
+        Ok(())
     }
 
     fn is_empty(&self) -> bool {
@@ -99,13 +163,19 @@ impl Namespace {
     }
 }
 
-impl ToString for Namespace {
-    fn to_string(&self) -> String {
-        self.parts.join("_")
+impl Default for Namespace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for Namespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.parts.join("_"))
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 enum ArgumentPassing {
     Reference,
     MutableReference,
@@ -113,22 +183,7 @@ enum ArgumentPassing {
     Clone,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct Modifier {
-    is_mutable: bool,
-    is_reference: bool,
-}
-
-impl Modifier {
-    fn nothing() -> Self {
-        Self {
-            is_mutable: false,
-            is_reference: false,
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub enum ArgumentType {
     Type {
         name: String,
@@ -141,65 +196,342 @@ pub enum ArgumentType {
         #[serde(rename = "type_parameters")]
         arg_type_parameters: Option<Vec<Box<ArgumentType>>>,
     },
-    //TODO: Tuple 
-    // Tuple {
-    //    type_parameters: Vec<Box<ArgumentType>>,
-    //},
-    //TODO: Enum 
+
+    /// (A, B, ...)
+    Tuple {
+        elements: Vec<Box<ArgumentType>>,
+    },
+
+    /// [T; length]
+    Array {
+        element: Box<ArgumentType>,
+        length: usize,
+    },
+
+    /// [T]. Objects are constructed as Vec<T> and coerced to &[T] when passed by reference.
+    /// Slices can only be passed by reference (wrapped in `Reference`).
+    Slice {
+        element: Box<ArgumentType>,
+    },
+
+    /// &T or &mut T. The referenced object is constructed and borrowed when passed.
+    Reference {
+        element: Box<ArgumentType>,
+        #[serde(default)]
+        is_mutable: bool,
+    },
+
+    /// Option<T>
+    Option {
+        element: Box<ArgumentType>,
+    },
+
+    /// A unit variant of enum type `name`, e.g. `my_crate::Mode::Fast`.
+    /// The argument is always that variant, so it has no `construction`.
+    EnumVariant {
+        name: String,
+        variant: String,
+        #[serde(rename = "type_parameters")]
+        arg_type_parameters: Option<Vec<Box<ArgumentType>>>,
+    },
 }
 
 impl ArgumentType {
-    fn simple_type(name: &str) -> Box<ArgumentType> {
-        Box::new(ArgumentType::Type {
-            name: name.to_string(),
-            arg_type_parameters: None,
-        })
+    /// Slices are only valid behind a reference, e.g. `&[T]`.
+    fn contains_unreferenced_slice(&self) -> bool {
+        match self {
+            ArgumentType::Slice { .. } => true,
+            ArgumentType::Reference { element, .. } => match element.as_ref() {
+                ArgumentType::Slice { element } => element.contains_unreferenced_slice(),
+                element => element.contains_unreferenced_slice(),
+            },
+            ArgumentType::Tuple { elements } => elements.iter().any(|element| element.contains_unreferenced_slice()),
+            ArgumentType::Array { element, .. } | ArgumentType::Option { element } => element.contains_unreferenced_slice(),
+            ArgumentType::Type { arg_type_parameters, .. }
+            | ArgumentType::Generic { arg_type_parameters, .. }
+            | ArgumentType::EnumVariant { arg_type_parameters, .. } => {
+                arg_type_parameters.iter().flatten().any(|param| param.contains_unreferenced_slice())
+            }
+        }
     }
 
-    fn simple_type_with_simple_typ_args(name: &str, tp_names: Vec<&str>) -> Box<ArgumentType> {
-        Box::new(ArgumentType::Type {
-            name: name.to_string(),
-            arg_type_parameters: Some(
-                tp_names
-                    .iter()
-                    .map(|name| ArgumentType::simple_type(name))
-                    .collect(),
-            ),
-        })
+    fn is_enum_variant(&self) -> bool {
+        match self {
+            ArgumentType::Reference { element, .. } => element.is_enum_variant(),
+            ArgumentType::EnumVariant { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Rust type of an argument, with generics replaced by the types they are resolved to.
+    fn emit_type(&self, type_parameters: &HashMap<String, String>) -> Result<syn::Type, Error> {
+        match self {
+            ArgumentType::Type { name, arg_type_parameters } | ArgumentType::EnumVariant { name, arg_type_parameters, .. } => {
+                let type_arguments = Self::emit_types(arg_type_parameters.iter().flatten(), type_parameters)?;
+                Ok(emit_generic_type(emit_type_path(name)?, &type_arguments))
+            }
+
+            ArgumentType::Generic { name, arg_type_parameters } => {
+                let type_name = type_parameters
+                    .get(name)
+                    .ok_or_else(|| Error::msg(format!("Generic type '{}' was not resolved.", name)))?;
+                match arg_type_parameters {
+                    Some(params) if !params.is_empty() => {
+                        let type_arguments = Self::emit_types(params.iter(), type_parameters)?;
+                        Ok(emit_generic_type(parse_code(type_name, "type")?, &type_arguments))
+                    }
+                    _ => parse_code(type_name, "type"),
+                }
+            }
+
+            ArgumentType::Tuple { elements } => {
+                // (A) is not a tuple, (A,) is.
+                let elements = Self::emit_types(elements.iter(), type_parameters)?;
+                Ok(parse_quote!((#(#elements,)*)))
+            }
+
+            ArgumentType::Array { element, length } => {
+                let element = element.emit_type(type_parameters)?;
+                let length = proc_macro2::Literal::usize_unsuffixed(*length);
+                Ok(parse_quote!([#element; #length]))
+            }
+
+            ArgumentType::Slice { element } => {
+                let element = element.emit_type(type_parameters)?;
+                Ok(parse_quote!([#element]))
+            }
+
+            ArgumentType::Reference { element, is_mutable } => {
+                let element = element.emit_type(type_parameters)?;
+                Ok(if *is_mutable { parse_quote!(&mut #element) } else { parse_quote!(&#element) })
+            }
+
+            ArgumentType::Option { element } => {
+                let element = element.emit_type(type_parameters)?;
+                Ok(parse_quote!(Option<#element>))
+            }
+        }
+    }
+
+    fn emit_types<'a>(
+        arg_types: impl Iterator<Item = &'a Box<ArgumentType>>,
+        type_parameters: &HashMap<String, String>,
+    ) -> Result<Vec<syn::Type>, Error> {
+        arg_types.map(|arg_type| arg_type.emit_type(type_parameters)).collect()
+    }
+
+    /// Type of the local an argument is constructed into.
+    /// References are constructed as the referenced object and slices as Vec, both are borrowed when passed.
+    fn emit_object_type(&self, type_parameters: &HashMap<String, String>) -> Result<syn::Type, Error> {
+        match self {
+            ArgumentType::Reference { element, .. } => element.emit_object_type(type_parameters),
+            ArgumentType::Slice { element } => {
+                let element = element.emit_type(type_parameters)?;
+                Ok(parse_quote!(Vec<#element>))
+            }
+            _ => self.emit_type(type_parameters),
+        }
+    }
+
+    /// Types that are not described by a package (tuples, arrays, ...) can only be built from Json or Default.
+    fn structural_constructor(constructor_name: &str) -> Option<Constructor> {
+        match constructor_name {
+            "Json" => Some(Constructor::FromJson),
+            "Default" => Some(Constructor::FromDefault),
+            _ => Option::None,
+        }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 pub enum ArgumentConstruction {
     Constructor(String),
-    ExistingObject()
+    ExistingObject(),
+    /// A Json value converted to a typed local at build time, with the same rules and checks as embedded flow data
+    /// (see DataLiteralEmitter), e.g. 42 for u32 or [1, 2] for &[u8].
+    Literal(Value),
+    /// A constant Rust expression, e.g. `std::time::Duration::from_secs(1)`, emitted as a typed local.
+    /// Blocks, closures, loops, assignments and macros are rejected.
+    Expression(String),
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Argument {
     #[serde(rename = "type")]
     arg_type: Box<ArgumentType>,
     name: String,
     passing: ArgumentPassing,
-    construction: ArgumentConstruction,
+    /// Required for all arguments except enum variants.
+    construction: Option<ArgumentConstruction>,
+    /// Wrappers applied to the passed object, innermost first. E.g. [Box, Some] passes Some(Box::new(x)).
+    #[serde(default)]
+    wrapping: Vec<ArgumentWrapping>,
+    /// Trait the object is coerced to by the first pointer wrapper, e.g. "my_crate::Filter" for Box<dyn my_crate::Filter>.
+    trait_object: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub enum ArgumentWrapping {
+    Box,
+    Arc,
+    ArcMutex,
+    Rc,
+    Some,
+}
+
+/// Parses a fragment of generated code taken from a description (names, types, expressions).
+/// Malformed fragments result in an error instead of code that does not compile.
+fn parse_code<T: syn::parse::Parse>(code: &str, kind: &str) -> Result<T, Error> {
+    syn::parse_str::<T>(code).map_err(|err| Error::msg(format!("Invalid {} '{}': {}", kind, code, err)))
+}
+
+/// Path of a described type, built from the segments of its name, e.g. `my_crate::MyType` for "my_crate::MyType".
+pub(crate) fn emit_type_path(type_name: &str) -> Result<syn::Path, Error> {
+    let segments = type_name
+        .split("::")
+        .map(syn::parse_str::<Ident>)
+        .collect::<Result<Vec<Ident>, syn::Error>>()
+        .map_err(|_| Error::msg(format!("Invalid type '{}'.", type_name)))?;
+    Ok(parse_quote!(#(#segments)::*))
+}
+
+/// `path` with the given type arguments, e.g. `my_crate::MyType<i32>`.
+pub(crate) fn emit_generic_type(path: syn::Path, type_arguments: &[syn::Type]) -> syn::Type {
+    if type_arguments.is_empty() {
+        parse_quote!(#path)
+    } else {
+        parse_quote!(#path<#(#type_arguments),*>)
+    }
+}
+
+/// Finds constructs in an expression that could run arbitrary code in the generated crate.
+struct ForbiddenExpressionFinder {
+    found: Option<&'static str>,
+}
+
+impl<'ast> syn::visit::Visit<'ast> for ForbiddenExpressionFinder {
+    fn visit_expr(&mut self, expr: &'ast syn::Expr) {
+        let forbidden = match expr {
+            syn::Expr::Block(_) => Some("blocks"),
+            syn::Expr::Unsafe(_) => Some("unsafe blocks"),
+            syn::Expr::Closure(_) => Some("closures"),
+            syn::Expr::Loop(_) | syn::Expr::While(_) | syn::Expr::ForLoop(_) => Some("loops"),
+            syn::Expr::Async(_) => Some("async blocks"),
+            syn::Expr::Assign(_) | syn::Expr::AssignOp(_) => Some("assignments"),
+            _ => Option::None,
+        };
+        if forbidden.is_some() {
+            self.found = self.found.or(forbidden);
+        }
+        syn::visit::visit_expr(self, expr);
+    }
+
+    fn visit_macro(&mut self, _mac: &'ast syn::Macro) {
+        self.found = self.found.or(Some("macros"));
+    }
+}
+
+/// Parses an expression from a description. Blocks, closures, loops, assignments and macros are rejected,
+/// the generated code additionally evaluates the expression in a `const` (see emit_value_arg_code).
+pub(crate) fn parse_expression(expression: &str) -> Result<syn::Expr, Error> {
+    let expr: syn::Expr = parse_code(expression, "expression")?;
+
+    let mut finder = ForbiddenExpressionFinder { found: Option::None };
+    syn::visit::Visit::visit_expr(&mut finder, &expr);
+    match finder.found {
+        Some(forbidden) => Err(Error::msg(format!("Invalid expression '{}': {} are not allowed.", expression, forbidden))),
+        None => Ok(expr),
+    }
+}
+
+impl ArgumentWrapping {
+    fn emit_code(&self, code: TokenStream) -> TokenStream {
+        match self {
+            Self::Box => quote!(Box::new(#code)),
+            Self::Arc => quote!(std::sync::Arc::new(#code)),
+            Self::ArcMutex => quote!(std::sync::Arc::new(std::sync::Mutex::new(#code))),
+            Self::Rc => quote!(std::rc::Rc::new(#code)),
+            Self::Some => quote!(Some(#code)),
+        }
+    }
+
+    fn is_pointer(&self) -> bool {
+        !matches!(self, Self::Some)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Box => "Box",
+            Self::Arc => "Arc",
+            Self::ArcMutex => "ArcMutex",
+            Self::Rc => "Rc",
+            Self::Some => "Some",
+        }
+    }
+
+    /// Pointer type a wrapped object can be coerced to. None if the wrapper is not a pointer.
+    fn emit_trait_object_type(&self, trait_object: &syn::Type) -> Option<TokenStream> {
+        match self {
+            Self::Box => Some(quote!(Box<#trait_object>)),
+            Self::Arc => Some(quote!(std::sync::Arc<#trait_object>)),
+            Self::ArcMutex => Some(quote!(std::sync::Arc<std::sync::Mutex<#trait_object>>)),
+            Self::Rc => Some(quote!(std::rc::Rc<#trait_object>)),
+            Self::Some => Option::None,
+        }
+    }
 }
 
 impl Argument {
-    fn emit_prefix_code(&self) -> String {
+    fn emit_prefix_code(&self) -> TokenStream {
+        if let ArgumentType::Reference { is_mutable, .. } = self.arg_type.as_ref() {
+            return if *is_mutable { quote!(&mut) } else { quote!(&) };
+        }
+
         match self.passing {
-            ArgumentPassing::Move => "".to_string(),
-            ArgumentPassing::Clone => "".to_string(),
-            ArgumentPassing::MutableReference => "&mut ".to_string(),
-            ArgumentPassing::Reference => "&".to_string(),
+            ArgumentPassing::Move => quote!(),
+            ArgumentPassing::Clone => quote!(),
+            ArgumentPassing::MutableReference => quote!(&mut),
+            ArgumentPassing::Reference => quote!(&),
+        }
+    }
+
+    fn emit_passing_code(&self, object_name: &Ident) -> Result<TokenStream, Error> {
+        let prefix = self.emit_prefix_code();
+        let postfix = self.emit_postfix_code();
+        let mut code = quote!(#prefix #object_name #postfix);
+
+        let mut trait_object = match &self.trait_object {
+            Some(trait_name) => Some(parse_code::<syn::Type>(&format!("dyn {}", trait_name), "trait object")?),
+            None => Option::None,
+        };
+        for wrapping in &self.wrapping {
+            code = wrapping.emit_code(code);
+
+            if let Some(trait_object_bound) = &trait_object {
+                if let Some(trait_object_type) = wrapping.emit_trait_object_type(trait_object_bound) {
+                    code = quote!(#code as #trait_object_type);
+                    trait_object = Option::None;
+                }
+            }
+        }
+
+        if trait_object.is_some() {
+            return Err(Error::msg(format!(
+                "Argument '{}' cannot be coerced to trait object 'dyn {}' without a Box, Arc, ArcMutex or Rc wrapping.",
+                self.name,
+                self.trait_object.clone().unwrap_or_default()
+            )));
         }
+
+        Ok(code)
     }
 
-    fn emit_postfix_code(&self) -> String {
+    fn emit_postfix_code(&self) -> TokenStream {
         match self.passing {
-            ArgumentPassing::Move => "".to_string(),
-            ArgumentPassing::Clone => ".clone()".to_string(),
-            ArgumentPassing::MutableReference => "".to_string(),
-            ArgumentPassing::Reference => "".to_string(),
+            ArgumentPassing::Move => quote!(),
+            ArgumentPassing::Clone => quote!(.clone()),
+            ArgumentPassing::MutableReference => quote!(),
+            ArgumentPassing::Reference => quote!(),
         }
     }
 
@@ -211,7 +543,9 @@ impl Argument {
             }),
             name: "change_observer".to_string(),
             passing: ArgumentPassing::Clone,
-            construction: ArgumentConstruction::ExistingObject(),
+            construction: Some(ArgumentConstruction::ExistingObject()),
+            wrapping: Vec::new(),
+            trait_object: Option::None,
         }
     }
 
@@ -223,35 +557,36 @@ impl Argument {
             }),
             name: "context".to_string(),
             passing: ArgumentPassing::Clone,
-            construction: ArgumentConstruction::ExistingObject(),
+            construction: Some(ArgumentConstruction::ExistingObject()),
+            wrapping: Vec::new(),
+            trait_object: Option::None,
         }
     }
 
-    fn into_object_description(&self, type_name: &String , type_parameter_part: &String) -> ObjectDescription {
+    fn to_object_description(&self, type_name: &str, object_type: syn::Type) -> ObjectDescription {
         ObjectDescription {
-            type_name: type_name.clone(),
-            type_parameter_part: type_parameter_part.clone(),
-            //type_parameters: HashMap::new(),
+            type_name: type_name.to_string(),
+            object_type,
             name: self.name.clone(),
-            is_mutable: if let ArgumentPassing::MutableReference { .. } = self.passing {
-                true
-            } else {
-                false
-            },
+            is_mutable: matches!(self.passing, ArgumentPassing::MutableReference),
+            argument_overrides: HashMap::new(),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Clone)]
 pub struct ObjectDescription {
+    /// Name the type is described by in its package.
     pub type_name: String,
-    pub type_parameter_part: String,
-    //pub type_parameters: HashMap<String, String>,
+    /// Type of the object including its type arguments, e.g. `my_crate::MyType<i32>`.
+    pub object_type: syn::Type,
     pub name: String,
     pub is_mutable: bool,
+    /// Replaces the construction of the constructor arguments with the given names.
+    pub argument_overrides: HashMap<String, ArgumentConstruction>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub enum Constructor {
     New{function_name: Option<String>},
     NewWithObserver{function_name: Option<String>},
@@ -259,392 +594,907 @@ pub enum Constructor {
     NewWithArbitraryArgs{function_name: Option<String>, arguments: Vec<Argument>},
     FromJson,
     FromDefault,
+    Builder(BuilderDescription),
+}
+
+/// `Type::builder(arguments).setter(..)...build()`, followed by method calls on the built object.
+/// `function_name` defaults to "builder", `build_function_name` to "build".
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct BuilderDescription {
+    pub function_name: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<Argument>,
+    #[serde(default)]
+    pub setters: Vec<MethodCall>,
+    pub build_function_name: Option<String>,
+    #[serde(default)]
+    pub calls: Vec<MethodCall>,
+}
+
+/// Call of a method with arguments, used for builder setters and post-construction calls.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct MethodCall {
+    pub function_name: String,
+    #[serde(default)]
+    pub arguments: Vec<Argument>,
+}
+
+/// Everything the code of one constructor and the constructors of its arguments is emitted with.
+struct EmitContext<'a, 'd> {
+    type_parameters: &'a HashMap<String, String>,
+    pack_man: &'a PackageManager,
+    data_source: &'a JsonDataSource<'d>,
 }
 
 impl Constructor {
-   
-    fn emit_fully_qualified_name(&self, name: &String, namespace: &Namespace, ignore: bool) -> String {
+
+    fn emit_object_ident(&self, name: &str, namespace: &Namespace, ignore: bool) -> Result<Ident, Error> {
+        let name_ident: Ident = parse_code(name, "object name")?;
         if namespace.is_empty() || ignore {
-            name.clone()
+            Ok(name_ident)
         } else {
-            format!("{}_{}", namespace.to_string(), name)
+            Ok(format_ident!("{}_{}", namespace.to_string(), name_ident))
         }
     }
 
-    fn emit_args(&self, args: &Vec<Argument>, current_namespace: &Namespace) -> String {
-        args.iter()
+    fn emit_args(&self, args: &[Argument], current_namespace: &Namespace) -> Result<TokenStream, Error> {
+        let arg_codes = args.iter()
             .map(|arg| {
-                format!(
-                    "{}{}{}",
-                    arg.emit_prefix_code(),
-                    self.emit_fully_qualified_name(&arg.name, current_namespace, matches!(arg.construction,ArgumentConstruction::ExistingObject())),
-                    arg.emit_postfix_code()
+                arg.emit_passing_code(
+                    &self.emit_object_ident(&arg.name, current_namespace, matches!(arg.construction, Some(ArgumentConstruction::ExistingObject())))?,
                 )
             })
-            .collect::<Vec<String>>()
-            .join(", ")
+            .collect::<Result<Vec<TokenStream>, Error>>()?;
+        Ok(quote!(#(#arg_codes),*))
     }
 
-    fn get_resolved_arg_type_parameters(
+    /// Resolves the constructor of an argument and the description of the object it constructs.
+    fn resolve_arg_construction(
         &self,
-        arg_type: &Box<ArgumentType>,
-        already_resolved_tps: &HashMap<String, String>,
-        resolved_tps: &mut HashMap<String, String>,
-    ) {
+        arg: &Argument,
+        arg_constructor_name: &str,
+        pack_man: &PackageManager,
+        type_parameters: &HashMap<String, String>,
+    ) -> Result<(Constructor, ObjectDescription), Error> {
+
+        match arg.arg_type.as_ref() {
 
-        let mut type_params: &Option<Vec<Box<ArgumentType>>> = &None;
+            ArgumentType::Type { name, .. } => {
+                let type_desc = pack_man.get_type(name).ok_or_else(|| {
+                    Error::msg(format!("Type description for '{}' not found.", name))
+                })?;
 
-        match arg_type.as_ref() {
-            ArgumentType::Type { arg_type_parameters: type_parameters, ..} => {
-                type_params = type_parameters;
-            }
-            
-            ArgumentType::Generic { arg_type_parameters: type_parameters, name } => {
-                if let Some(resolved_name) = already_resolved_tps.get(name) {
-                    resolved_tps.insert(name.clone(), resolved_name.clone());
-                }
-                type_params = type_parameters; 
-            }
-        }
+                let arg_constructor = type_desc.constructors.get(arg_constructor_name).ok_or_else(|| {
+                    Error::msg(format!("Constructor '{}' for type '{}' not found.", arg_constructor_name, name))
+                })?;
 
-        if let Some(params) = type_params {
-            for param in params {
-                self.get_resolved_arg_type_parameters(
-                    &param,
-                    already_resolved_tps,
-                    resolved_tps,
-                );
+                let object_desc = arg.to_object_description(name, arg.arg_type.emit_type(type_parameters)?);
+                Ok((arg_constructor.clone(), object_desc))
             }
-        }
 
-    }
+            ArgumentType::Generic { name, .. } => {
+                let type_name = type_parameters.get(name).ok_or_else(|| {
+                    Error::msg(format!("Generic type '{}' was not resolved.", name))
+                })?;
 
-    fn emit_arg_type_parameters_part_rec(
-        &self,
-        tp_part: &mut String,
-        argument_type: &Box<ArgumentType>,
-        resolved_type_parameters: &HashMap<String, String>,
-    ) {
-        let mut arg_type_params: &Option<Vec<Box<ArgumentType>>> = &None;
+                let type_desc = pack_man.get_type(type_name).ok_or_else(|| {
+                    Error::msg(format!("Type description for '{}' not found", type_name))
+                })?;
 
-        match argument_type.as_ref() {
+                let arg_constructor = type_desc.constructors.get(arg_constructor_name).ok_or_else(|| {
+                    Error::msg(format!("Constructor '{}' for type '{}' not found.", arg_constructor_name, name))
+                })?;
 
-            ArgumentType::Type { name, arg_type_parameters: type_parameters} => {
-                tp_part.push_str(name);
-                arg_type_params = type_parameters;
+                let object_desc = arg.to_object_description(type_name, arg.arg_type.emit_type(type_parameters)?);
+                Ok((arg_constructor.clone(), object_desc))
             }
 
-            ArgumentType::Generic { name, arg_type_parameters: type_parameters} => {
-                if let Some(tn) = resolved_type_parameters.get(name) {
-                    tp_part.push_str(tn);
-                } else {
-                    //TODO: Error Handling
-                }
-                arg_type_params = type_parameters;
-            }            
-        }
+            ArgumentType::EnumVariant { .. } => Err(Error::msg(format!(
+                "Enum variant argument '{}' is not constructed, remove its construction.",
+                arg.name
+            ))),
 
-        if let Some(params) = arg_type_params {
-            if !params.is_empty() {
-                tp_part.push_str("<");
+            ArgumentType::Reference { element, is_mutable } => {
+                // Construct the referenced object. It is borrowed when passed (see Argument::emit_prefix_code).
+                let referenced_arg = Argument {
+                    arg_type: element.clone(),
+                    passing: if *is_mutable { ArgumentPassing::MutableReference } else { ArgumentPassing::Reference },
+                    ..arg.clone()
+                };
 
-                for tp in params {
-                    self.emit_arg_type_parameters_part_rec(tp_part, &tp, resolved_type_parameters);
-                    tp_part.push_str(",")
-                }
+                self.resolve_arg_construction(&referenced_arg, arg_constructor_name, pack_man, type_parameters)
+            }
 
-                tp_part.pop(); // pop last ,
-                tp_part.push_str(">");
+            ArgumentType::Tuple { .. }
+            | ArgumentType::Array { .. }
+            | ArgumentType::Slice { .. }
+            | ArgumentType::Option { .. } => {
+                let arg_constructor = ArgumentType::structural_constructor(arg_constructor_name).ok_or_else(|| {
+                    Error::msg(format!(
+                        "Constructor '{}' not available for argument '{}'. Tuples, arrays, slices and options can only be constructed with 'Json' or 'Default'.",
+                        arg_constructor_name, arg.name
+                    ))
+                })?;
+
+                let object_type = arg.arg_type.emit_object_type(type_parameters)?;
+                let type_name = quote!(#object_type).to_string();
+                Ok((arg_constructor, arg.to_object_description(&type_name, object_type)))
             }
         }
     }
 
-    fn emit_arg_type_parameters_part(
+    fn emit_arg_construction_code(
+        &self,
+        arg: &Argument,
+        arg_constructor_name: &str,
+        current_namespace: &Namespace,
+        ctx: &EmitContext,
+    ) -> Result<TokenStream, Error> {
+        let (arg_constructor, object_desc) = self.resolve_arg_construction(arg, arg_constructor_name, ctx.pack_man, ctx.type_parameters)?;
+        arg_constructor.emit_code(&object_desc, current_namespace, ctx)
+    }
+
+    fn emit_enum_variant_code(
         &self,
-        arg_type_parameters: &Option<Vec<Box<ArgumentType>>>,
+        arg: &Argument,
+        current_namespace: &Namespace,
         type_parameters: &HashMap<String, String>,
-    ) -> String {
-                
-        if let Some(arg_type_params) = arg_type_parameters {
-           
-            if !arg_type_params.is_empty() { 
-                let mut tp_part = "<".to_string();
-
-                for tp in arg_type_params {
-                    self.emit_arg_type_parameters_part_rec(&mut tp_part, tp, type_parameters);
-                    tp_part.push_str(",")
-                }
+    ) -> Result<TokenStream, Error> {
+        let mut arg_type = arg.arg_type.as_ref();
+        while let ArgumentType::Reference { element, .. } = arg_type {
+            arg_type = element.as_ref();
+        }
 
-                //tp_part.pop(); // pop last ,
-                tp_part.push_str(">");
-                return tp_part
-            }
+        if let ArgumentType::EnumVariant { name, variant, .. } = arg_type {
+            let mutability = self.emit_mutable(matches!(arg.passing, ArgumentPassing::MutableReference)
+                || matches!(arg.arg_type.as_ref(), ArgumentType::Reference { is_mutable: true, .. }));
+            let object_ident = self.emit_object_ident(&arg.name, current_namespace, false)?;
+            let enum_type = arg_type.emit_type(type_parameters)?;
+            let enum_path = emit_type_path(name)?;
+            let variant_ident: Ident = parse_code(variant, "enum variant")?;
+
+            Ok(quote!(let #mutability #object_ident: #enum_type = #enum_path::#variant_ident;))
+        } else {
+            Err(Error::msg(format!("Argument '{}' has no construction.", arg.name)))
         }
-        
-        "".to_string()        
     }
 
-    fn emit_arg_construction_code(
+    fn emit_args_construction_code(
         &self,
-        arg: &Argument,
-        arg_constructor_name: String,
-        pack_man: &PackageManager,
+        args: &[Argument],
         current_namespace: &Namespace,
-        type_parameters: &HashMap<String, String>,
-    ) -> Result<String, Error> {
+        ctx: &EmitContext,
+    ) -> Result<TokenStream, Error> {
+        let mut construction_blocks = TokenStream::new();
 
+        for arg in args {
+            if arg.arg_type.contains_unreferenced_slice() {
+                return Err(Error::msg(format!("Argument '{}': slices can only be passed by reference.", arg.name)));
+            }
 
-        match arg.arg_type.as_ref() {
+            // Existing objects do not need to be constructed.
+            match &arg.construction {
+                Some(ArgumentConstruction::Constructor(constructor_name)) => {
+                    // Generate construction for each argument.
+                    construction_blocks.extend(self.emit_arg_construction_code(arg, constructor_name, current_namespace, ctx)?);
+                }
 
-            ArgumentType::Type {
-                name,
-                arg_type_parameters,
-            } => {
-                if let Some(type_desc) = pack_man.get_type(name) {
-                    
-                    if let Some(arg_constructor) = type_desc.constructors.get(&arg_constructor_name) {
-   
-                        let object_desc = arg.into_object_description(
-                            &name, 
-                            &self.emit_arg_type_parameters_part(arg_type_parameters, type_parameters), 
-                        );
+                Some(ArgumentConstruction::Literal(value)) => {
+                    let object_type = arg.arg_type.emit_object_type(ctx.type_parameters)?;
+                    let literal_emitter = DataLiteralEmitter { pack_man: ctx.pack_man, allow_alloc: ctx.data_source.allows_alloc() };
+                    let literal_code = literal_emitter.emit_literal(value, &object_type, &format!("Argument '{}'", arg.name))?;
+                    construction_blocks.extend(self.emit_value_arg_code(arg, literal_code, current_namespace, ctx.type_parameters)?);
+                }
 
-                        arg_constructor.emit_code_template(&object_desc, type_parameters, pack_man, current_namespace)
-                    } else {
-                        Err(Error::msg(format!(
-                            "Constructor '{}' for type '{}' not found.", arg_constructor_name, name 
-                        ))) 
-                    }
-                } else {
-                    Err(Error::msg(format!(
-                        "Type description for '{}' not found.", name
-                    )))
+                Some(ArgumentConstruction::Expression(expression)) => {
+                    // Evaluated in a const, so the compiler rejects anything that is not a constant expression.
+                    let expr = parse_expression(expression)?;
+                    let object_type = arg.arg_type.emit_object_type(ctx.type_parameters)?;
+                    let expression_code = quote!({ const VALUE: #object_type = #expr; VALUE });
+                    construction_blocks.extend(self.emit_value_arg_code(arg, expression_code, current_namespace, ctx.type_parameters)?);
                 }
-            }
 
-            ArgumentType::Generic { name , arg_type_parameters }=> {
+                Some(ArgumentConstruction::ExistingObject()) => {}
 
-                // get resolved type parameters.
-                
-                /*
-                let mut type_parameters_for_arg_type = HashMap::<String, String>::new();
-                self.get_resolved_arg_type_parameters(
-                    &arg.arg_type,
-                    type_parameters,
-                    &mut type_parameters_for_arg_type,
-                );
-                 */
-                
-
-                // check if generic was already resolved. if so, try to get type and emit constructor code.
-                // TODO: Think about what should happen if it is not yet resolved.
-                if let Some(type_name) = type_parameters.get(name) {
-                    if let Some(type_desc) = pack_man.get_type(&type_name) {    
-                        if let Some(arg_constructor) = type_desc.constructors.get(&arg_constructor_name) {
-
-                            let object_desc = arg.into_object_description(
-                                &type_name, 
-                                &self.emit_arg_type_parameters_part(arg_type_parameters, &type_parameters),
-                            );
-
-                            arg_constructor.emit_code_template(
-                                &object_desc,
-                                &type_parameters,
-                                pack_man,
-                                current_namespace,
-                            )
-                        } else {
-                            Err(Error::msg(format!(
-                                "Constructor '{}' for type '{}' not found.", arg_constructor_name, name 
-                            ))) 
-                        }
-                    } else {
-                        Err(Error::msg(format!(
-                            "Type description for '{}' not found",
-                            type_name
-                        )))
-                    }
-                } else {
-                    Err(Error::msg("Generic type was not resolved"))
-                }
+                None => construction_blocks.extend(self.emit_enum_variant_code(arg, current_namespace, ctx.type_parameters)?),
             }
         }
+
+        Ok(construction_blocks)
     }
 
-    fn emit_args_construction_code(
+    fn emit_value_arg_code(
         &self,
-        pack_man: &PackageManager,
-        args: &Vec<Argument>,
+        arg: &Argument,
+        value_code: TokenStream,
         current_namespace: &Namespace,
         type_parameters: &HashMap<String, String>,
-    ) -> Result<String, Error> {
-        let mut construction_blocks = Vec::<String>::new();
-        
-        for arg in args {
-            // Only objects with a constructor need to be constructed.
-            if let ArgumentConstruction::Constructor(constructor_name) = &arg.construction {
-
-                // Generate construction for each argument.
-                match self.emit_arg_construction_code(
-                    arg,
-                    constructor_name.clone(),
-                    pack_man,
-                    current_namespace,
-                    type_parameters,
-                ) {
-                    Ok(code) => construction_blocks.push(code),
-                    Err(err) => return Err(err),
-                }
-            }
-        }
+    ) -> Result<TokenStream, Error> {
+        let mutability = self.emit_mutable(matches!(arg.passing, ArgumentPassing::MutableReference)
+            || matches!(arg.arg_type.as_ref(), ArgumentType::Reference { is_mutable: true, .. }));
+        let object_ident = self.emit_object_ident(&arg.name, current_namespace, false)?;
+        let object_type = arg.arg_type.emit_object_type(type_parameters)?;
 
-        Ok(construction_blocks.join("\n"))
+        Ok(quote!(let #mutability #object_ident: #object_type = #value_code;))
     }
 
-    fn emit_mutable(&self, is_mutable: bool) -> String {
+    fn emit_mutable(&self, is_mutable: bool) -> TokenStream {
         if is_mutable {
-            " mut".to_string()
+            quote!(mut)
         } else {
-            "".to_string()
+            quote!()
         }
     }
 
-    fn emit_function_name(&self, function_name: &Option<String>) -> String {
-        if let Some(func_name) = function_name {
-            func_name.clone()
-        } else {
-            "new".into()
+    fn emit_function_name(&self, function_name: &Option<String>, default_name: &str) -> Result<Ident, Error> {
+        parse_code(function_name.as_deref().unwrap_or(default_name), "function name")
+    }
+
+    /// Path of a described object's type in expression position, e.g. `my_crate::MyType::<i32>`.
+    fn emit_object_description_path(&self, od: &ObjectDescription) -> Result<syn::Path, Error> {
+        match &od.object_type {
+            syn::Type::Path(type_path) if type_path.qself.is_none() => {
+                let mut path = type_path.path.clone();
+                for segment in path.segments.iter_mut() {
+                    if let syn::PathArguments::AngleBracketed(type_arguments) = &mut segment.arguments {
+                        type_arguments.colon2_token = Some(Default::default());
+                    }
+                }
+                Ok(path)
+            }
+            object_type => Err(Error::msg(format!(
+                "Objects of type '{}' cannot be constructed by a function.",
+                quote!(#object_type)
+            ))),
         }
     }
 
     fn emit_new_with_args(
         &self,
         od: &ObjectDescription,
-        type_parameters: &HashMap<String, String>,
         function_name: &Option<String>,
-        pack_man: &PackageManager,
-        args: &Vec<Argument>,
+        args: &[Argument],
         current_namespace: &Namespace,
-    ) -> Result<String, Error> {
-            
-            let mut new_namespace = current_namespace.clone();
-            new_namespace.add_part(&od.name);
-
-            let args_construction_code = self.emit_args_construction_code(
-                pack_man,
-                args,
-                &new_namespace,
-                type_parameters,
-            )?;
-
-            Ok(format!(
-                "{}\n let{} {} = {}::{}{}({});",
-                args_construction_code,
-                self.emit_mutable(od.is_mutable),
-                self.emit_fully_qualified_name(&od.name, current_namespace, false),
-                od.type_name,
-                if od.type_parameter_part.is_empty() {"".to_string() } else {od.type_parameter_part.clone() + "::"} ,
-                self.emit_function_name(function_name),
-                self.emit_args(args, &new_namespace)
-            ))
-        
+        ctx: &EmitContext,
+    ) -> Result<TokenStream, Error> {
+        let mut new_namespace = current_namespace.clone();
+        new_namespace.add_part(&od.name)?;
+
+        let args = &self.apply_argument_overrides(args, od, "");
+        let args_construction_code = self.emit_args_construction_code(args, &new_namespace, ctx)?;
+
+        let mutability = self.emit_mutable(od.is_mutable);
+        let object_ident = self.emit_object_ident(&od.name, current_namespace, false)?;
+        let type_path = self.emit_object_description_path(od)?;
+        let function_ident = self.emit_function_name(function_name, "new")?;
+        let args_code = self.emit_args(args, &new_namespace)?;
+
+        Ok(quote! {
+            #args_construction_code
+            let #mutability #object_ident = #type_path::#function_ident(#args_code);
+        })
     }
-    
 
-    fn emit_default(
-        &self,
-        od: &ObjectDescription,
-        pack_man: &PackageManager,
-        current_namespace: &Namespace,
-    ) -> Result<String, Error> {
-
-        Ok(format!(
-            "let{} {}:{}{} = Default::default();",
-            self.emit_mutable(od.is_mutable),
-            self.emit_fully_qualified_name(&od.name, current_namespace, false),
-            od.type_name,
-            od.type_parameter_part
-        ))       
-    }
-
-    fn emit_json_path(&self, cn: &Namespace, od: &ObjectDescription) -> String {
-        format!(
-            "{}[\"{}\"]",
-            cn.parts
-                .iter()
-                .map(|item| format!("[\"{}\"]", item))
-                .collect::<Vec<String>>()
-                .join(""),
-            od.name
-        )
-    }
-
-    fn emit_new_from_json(
+    fn apply_argument_overrides(&self, args: &[Argument], od: &ObjectDescription, prefix: &str) -> Vec<Argument> {
+        args.iter()
+            .map(|arg| match od.argument_overrides.get(&format!("{}{}", prefix, arg.name)) {
+                Some(construction) => Argument { construction: Some(construction.clone()), ..arg.clone() },
+                None => arg.clone(),
+            })
+            .collect()
+    }
+
+    /// Namespace parts for the arguments of method calls. Calls of the same method are numbered.
+    fn get_method_call_parts(&self, method_calls: &[&MethodCall]) -> Vec<String> {
+        method_calls
+            .iter()
+            .enumerate()
+            .map(|(index, call)| {
+                if method_calls.iter().filter(|c| c.function_name == call.function_name).count() > 1 {
+                    format!("{}_{}", call.function_name, index)
+                } else {
+                    call.function_name.clone()
+                }
+            })
+            .collect()
+    }
+
+    fn emit_builder(
         &self,
         od: &ObjectDescription,
+        builder: &BuilderDescription,
         current_namespace: &Namespace,
-    ) -> Result<String, Error> {
-        let full_object_name = self.emit_fully_qualified_name(&od.name, current_namespace, false);
+        ctx: &EmitContext,
+    ) -> Result<TokenStream, Error> {
+        let mut new_namespace = current_namespace.clone();
+        new_namespace.add_part(&od.name)?;
+
+        let args = self.apply_argument_overrides(&builder.arguments, od, "");
+        let mut construction_code = self.emit_args_construction_code(&args, &new_namespace, ctx)?;
+
+        // Setter and call arguments live in their own namespace, so equally named arguments do not clash.
+        // Overrides address them as "<method>.<argument>".
+        let method_calls: Vec<&MethodCall> = builder.setters.iter().chain(builder.calls.iter()).collect();
+        let mut method_call_codes = Vec::new();
+        for (method_call, part) in method_calls.iter().zip(self.get_method_call_parts(&method_calls)) {
+            let mut method_namespace = new_namespace.clone();
+            method_namespace.add_part(&part)?;
+
+            let method_args = self.apply_argument_overrides(&method_call.arguments, od, &format!("{}.", part));
+            construction_code.extend(self.emit_args_construction_code(&method_args, &method_namespace, ctx)?);
+
+            let method_ident: Ident = parse_code(&method_call.function_name, "method name")?;
+            let method_args_code = self.emit_args(&method_args, &method_namespace)?;
+            method_call_codes.push(quote!(#method_ident(#method_args_code)));
+        }
+
+        let mutability = self.emit_mutable(od.is_mutable || !builder.calls.is_empty());
+        let object_ident = self.emit_object_ident(&od.name, current_namespace, false)?;
+        let type_path = self.emit_object_description_path(od)?;
+        let builder_ident = self.emit_function_name(&builder.function_name, "builder")?;
+        let build_ident = self.emit_function_name(&builder.build_function_name, "build")?;
+        let args_code = self.emit_args(&args, &new_namespace)?;
 
-        Ok(format!(
-            "let{} {}: {}{} = serde_json::from_value(data{}.clone()).expect(\"Could not create '{}' from Json.\");",
-            self.emit_mutable(od.is_mutable),
-            full_object_name,
-            od.type_name,
-            od.type_parameter_part,
-            self.emit_json_path(current_namespace, od),
-            full_object_name
-        ))
+        let (setter_codes, call_codes) = method_call_codes.split_at(builder.setters.len());
+        let call_statements: Vec<TokenStream> = call_codes.iter().map(|code| quote!(#object_ident.#code;)).collect();
+
+        Ok(quote! {
+            #construction_code
+            let #mutability #object_ident = #type_path::#builder_ident(#args_code) #(.#setter_codes)* .#build_ident();
+            #(#call_statements)*
+        })
     }
-}
 
-impl Constructor  {
-    pub fn emit_code_template(
-        &self,
-        obj_desc: &ObjectDescription,
-        type_parameters: &HashMap<String, String>,
-        pack_man: &PackageManager,
-        namespace: &Namespace,
-    ) -> Result<String, Error> {
+    fn emit_default(&self, od: &ObjectDescription, current_namespace: &Namespace) -> Result<TokenStream, Error> {
+        let mutability = self.emit_mutable(od.is_mutable);
+        let object_ident = self.emit_object_ident(&od.name, current_namespace, false)?;
+        let object_type = &od.object_type;
 
-        
+        Ok(quote!(let #mutability #object_ident: #object_type = Default::default();))
+    }
 
-        match self {
+    fn emit_json_path(&self, cn: &Namespace, od: &ObjectDescription) -> TokenStream {
+        let keys = cn.parts.iter().chain(std::iter::once(&od.name));
+        quote!(data #([#keys])*)
+    }
 
-            Self::New {function_name} => self.emit_new_with_args(obj_desc, type_parameters, function_name, pack_man, &vec![], namespace),
+    fn emit_new_from_json(&self, od: &ObjectDescription, current_namespace: &Namespace, ctx: &EmitContext) -> Result<TokenStream, Error> {
+        let mutability = self.emit_mutable(od.is_mutable);
+        let object_ident = self.emit_object_ident(&od.name, current_namespace, false)?;
+        let object_type = &od.object_type;
 
-            Self::NewWithObserver{function_name} => self.emit_new_with_args(
-                obj_desc,
-                type_parameters,
-                function_name,
-                pack_man,
-                &vec![Argument::new_change_observer_arg()],
-                namespace,
-            ),
+        let keys: Vec<String> = current_namespace.parts.iter().chain(std::iter::once(&od.name)).cloned().collect();
+        let data_path = format!("$.{}", keys.join("."));
 
-            Self::NewWithObserverAndContext{function_name} => self.emit_new_with_args(
-                obj_desc,
-                type_parameters,
-                function_name,
-                pack_man,
-                &vec![
-                    Argument::new_change_observer_arg(),
-                    Argument::new_context_arg(),
-                ],
-                namespace,
-            ),
+        match ctx.data_source {
+            JsonDataSource::Literal { data, allow_alloc } => {
+                let value = keys
+                    .iter()
+                    .try_fold(*data, |value, key| value.get(key))
+                    .ok_or_else(|| Error::msg(format!("No flow data at '{}' for '{}'.", data_path, object_ident)))?;
 
-            Self::NewWithArbitraryArgs{function_name, arguments} => {
-                self.emit_new_with_args(obj_desc, type_parameters, function_name, pack_man, arguments, namespace)
+                let literal_emitter = DataLiteralEmitter { pack_man: ctx.pack_man, allow_alloc: *allow_alloc };
+                let literal = literal_emitter.emit_literal(value, object_type, &data_path)?;
+                return Ok(quote!(let #mutability #object_ident: #object_type = #literal;));
             }
 
-            Self::FromJson => self.emit_new_from_json(obj_desc, namespace),
+            JsonDataSource::Embedded { data, fallbacks } => {
+                let literal_emitter = DataLiteralEmitter { pack_man: ctx.pack_man, allow_alloc: true };
+                // Only types that have no literal form are parsed at runtime, data that does not fit its type is an error.
+                let literal = match keys.iter().try_fold(*data, |value, key| value.get(key)) {
+                    Some(value) => match literal_emitter.emit_literal(value, object_type, &data_path) {
+                        Ok(literal) => Some(literal),
+                        Err(err) if err.is::<UnsupportedType>() => Option::None,
+                        Err(err) => return Err(err),
+                    },
+                    Option::None => Option::None,
+                };
+
+                if let Some(literal) = literal {
+                    return Ok(quote!(let #mutability #object_ident: #object_type = #literal;));
+                }
+                fallbacks.borrow_mut().push(keys);
+            }
 
-            Self::FromDefault => self.emit_default(obj_desc, pack_man, namespace),
-            
+            JsonDataSource::Runtime => {}
         }
-    }
-}
+
+        let json_path = self.emit_json_path(current_namespace, od);
+        let error_message = format!("Could not create '{}' from Json: {{}}", object_ident);
+
+        Ok(quote! {
+            let #mutability #object_ident: #object_type = serde_json::from_value(#json_path.clone())
+                .map_err(|err| InitError::new(format!(#error_message, err)))?;
+        })
+    }
+
+    fn emit_code(&self, obj_desc: &ObjectDescription, namespace: &Namespace, ctx: &EmitContext) -> Result<TokenStream, Error> {
+        match self {
+            Self::New { function_name } => self.emit_new_with_args(obj_desc, function_name, &[], namespace, ctx),
+
+            Self::NewWithObserver { function_name } => {
+                self.emit_new_with_args(obj_desc, function_name, &[Argument::new_change_observer_arg()], namespace, ctx)
+            }
+
+            Self::NewWithObserverAndContext { function_name } => self.emit_new_with_args(
+                obj_desc,
+                function_name,
+                &[Argument::new_change_observer_arg(), Argument::new_context_arg()],
+                namespace,
+                ctx,
+            ),
+
+            Self::NewWithArbitraryArgs { function_name, arguments } => {
+                self.emit_new_with_args(obj_desc, function_name, arguments, namespace, ctx)
+            }
+
+            Self::FromJson => self.emit_new_from_json(obj_desc, namespace, ctx),
+
+            Self::FromDefault => self.emit_default(obj_desc, namespace),
+
+            Self::Builder(builder) => self.emit_builder(obj_desc, builder, namespace, ctx),
+        }
+    }
+}
+
+impl Constructor  {
+    /// Collects the flow data paths (see emit_json_path) of all objects this constructor builds from Json.
+    pub fn collect_json_data_bindings(
+        &self,
+        obj_desc: &ObjectDescription,
+        type_parameters: &HashMap<String, String>,
+        pack_man: &PackageManager,
+        namespace: &Namespace,
+        bindings: &mut Vec<JsonDataBinding>,
+    ) -> Result<(), Error> {
+        if let Self::FromJson = self {
+            let mut path = namespace.parts.clone();
+            path.push(obj_desc.name.clone());
+            bindings.push(JsonDataBinding { path, type_name: obj_desc.type_name.clone() });
+        }
+
+        let mut new_namespace = namespace.clone();
+        new_namespace.add_part(&obj_desc.name)?;
+
+        for (method_part, args) in self.get_argument_lists(obj_desc) {
+            let mut args_namespace = new_namespace.clone();
+            if let Some(method_part) = method_part {
+                args_namespace.add_part(&method_part)?;
+            }
+            self.collect_args_json_data_bindings(&args, type_parameters, pack_man, &args_namespace, bindings)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that this constructor and the constructors of its arguments need neither std nor alloc:
+    /// arguments must not be wrapped in pointers or built from expressions, and literals must not allocate.
+    pub fn check_no_std(
+        &self,
+        obj_desc: &ObjectDescription,
+        type_parameters: &HashMap<String, String>,
+        pack_man: &PackageManager,
+    ) -> Result<(), Error> {
+        for (_, args) in self.get_argument_lists(obj_desc) {
+            for arg in &args {
+                if let Some(wrapping) = arg.wrapping.iter().find(|wrapping| wrapping.is_pointer()) {
+                    return Err(Error::msg(format!(
+                        "Argument '{}' is wrapped in {}, which is not available without std.",
+                        arg.name,
+                        wrapping.name()
+                    )));
+                }
+
+                match &arg.construction {
+                    Some(ArgumentConstruction::Expression(_)) => {
+                        return Err(Error::msg(format!(
+                            "Argument '{}' is built from an expression, which cannot be checked to work without std.",
+                            arg.name
+                        )));
+                    }
+
+                    Some(ArgumentConstruction::Literal(value)) => {
+                        let object_type = arg.arg_type.emit_object_type(type_parameters)?;
+                        DataLiteralEmitter { pack_man, allow_alloc: false }.emit_literal(value, &object_type, &format!("Argument '{}'", arg.name))?;
+                    }
+
+                    Some(ArgumentConstruction::Constructor(constructor_name)) => {
+                        let (arg_constructor, object_desc) = self.resolve_arg_construction(arg, constructor_name, pack_man, type_parameters)?;
+                        arg_constructor.check_no_std(&object_desc, type_parameters, pack_man)?;
+                    }
+
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Arguments of the constructor and of builder setters and calls, with overrides applied.
+    /// Setter and call arguments come with the namespace part of their method (see get_method_call_parts).
+    fn get_argument_lists(&self, obj_desc: &ObjectDescription) -> Vec<(Option<String>, Vec<Argument>)> {
+        match self {
+            Self::NewWithArbitraryArgs { arguments, .. } => vec![(Option::None, self.apply_argument_overrides(arguments, obj_desc, ""))],
+
+            Self::Builder(builder) => {
+                let mut argument_lists = vec![(Option::None, self.apply_argument_overrides(&builder.arguments, obj_desc, ""))];
+                let method_calls: Vec<&MethodCall> = builder.setters.iter().chain(builder.calls.iter()).collect();
+                for (method_call, part) in method_calls.iter().zip(self.get_method_call_parts(&method_calls)) {
+                    let method_args = self.apply_argument_overrides(&method_call.arguments, obj_desc, &format!("{}.", part));
+                    argument_lists.push((Some(part), method_args));
+                }
+                argument_lists
+            }
+
+            _ => Vec::new(),
+        }
+    }
+
+    fn collect_args_json_data_bindings(
+        &self,
+        args: &[Argument],
+        type_parameters: &HashMap<String, String>,
+        pack_man: &PackageManager,
+        namespace: &Namespace,
+        bindings: &mut Vec<JsonDataBinding>,
+    ) -> Result<(), Error> {
+        for arg in args {
+            if let Some(ArgumentConstruction::Constructor(constructor_name)) = &arg.construction {
+                let (arg_constructor, object_desc) = self.resolve_arg_construction(arg, constructor_name, pack_man, type_parameters)?;
+                arg_constructor.collect_json_data_bindings(&object_desc, type_parameters, pack_man, namespace, bindings)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Emits the statements that construct the described object and all its constructor arguments.
+    pub fn emit_code_template(
+        &self,
+        obj_desc: &ObjectDescription,
+        type_parameters: &HashMap<String, String>,
+        pack_man: &PackageManager,
+        namespace: &Namespace,
+        data_source: &JsonDataSource,
+    ) -> Result<TokenStream, Error> {
+        self.emit_code(obj_desc, namespace, &EmitContext { type_parameters, pack_man, data_source })
+    }
+}
+
+/// A problem found while validating a package.
+/// `path` is the JSON path of the offending element within the package description.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ValidationIssue {
+    pub path: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    pub fn new(path: String, message: String) -> Self {
+        Self { path, message }
+    }
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Appends `key` to a JSON path, as `.key` if it is an identifier and as `['key']` otherwise (e.g. `flowrs-std`).
+pub(crate) fn json_path_child(path: &str, key: &str) -> String {
+    let is_identifier = matches!(key.chars().next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_identifier {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}['{}']", path, key.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+impl Package {
+    /// Checks that the constructors of all types only reference known types, declared generics and existing constructors.
+    /// Types are looked up in the package manager and in the package itself, so a package can be validated before it is added.
+    pub fn validate(&self, pack_man: &PackageManager) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        for (crate_name, cr) in &self.crates {
+            let crate_path = json_path_child("$.crates", crate_name);
+            self.validate_types(&cr.types, &crate_path, pack_man, &mut issues);
+            self.validate_modules(&cr.modules, &crate_path, pack_man, &mut issues);
+        }
+
+        issues.sort_by(|a, b| a.path.cmp(&b.path));
+        issues
+    }
+
+    fn resolve_type<'a>(&'a self, type_name: &str, pack_man: &'a PackageManager) -> Option<&'a Type> {
+        pack_man.get_type(type_name).or_else(|| {
+            let type_ids: Vec<&str> = type_name.split("::").collect();
+            pack_man.get_type_from_package(&type_ids, self)
+        })
+    }
+
+    fn validate_modules(
+        &self,
+        modules: &HashMap<String, Module>,
+        path: &str,
+        pack_man: &PackageManager,
+        issues: &mut Vec<ValidationIssue>,
+    ) {
+        for (module_name, module) in modules {
+            let module_path = json_path_child(&format!("{}.modules", path), module_name);
+            self.validate_types(&module.types, &module_path, pack_man, issues);
+            self.validate_modules(&module.modules, &module_path, pack_man, issues);
+        }
+    }
+
+    fn validate_types(
+        &self,
+        types: &HashMap<String, Type>,
+        path: &str,
+        pack_man: &PackageManager,
+        issues: &mut Vec<ValidationIssue>,
+    ) {
+        for (type_name, t) in types {
+            let type_path = json_path_child(&format!("{}.types", path), type_name);
+            let type_parameters = t.type_parameters.clone().unwrap_or_default();
+
+            if let Some(data_schema) = &t.data_schema {
+                if let Err(err) = jsonschema::JSONSchema::compile(data_schema) {
+                    issues.push(ValidationIssue::new(
+                        format!("{}.data_schema", type_path),
+                        format!("Invalid data schema: {}", err),
+                    ));
+                }
+            }
+
+            for (index, field) in t.fields.iter().flatten().enumerate() {
+                if let Err(err) = parse_code::<syn::Type>(&field.field_type, "field type") {
+                    issues.push(ValidationIssue::new(format!("{}.fields[{}].type", type_path, index), err.to_string()));
+                }
+            }
+
+            for (constructor_name, constructor) in &t.constructors {
+                let constructor_path = json_path_child(&format!("{}.constructors", type_path), constructor_name);
+
+                let mut argument_lists: Vec<(String, &Vec<Argument>)> = Vec::new();
+                match constructor {
+                    Constructor::NewWithArbitraryArgs { arguments, .. } => {
+                        argument_lists.push((format!("{}.NewWithArbitraryArgs.arguments", constructor_path), arguments));
+                    }
+                    Constructor::Builder(builder) => {
+                        argument_lists.push((format!("{}.Builder.arguments", constructor_path), &builder.arguments));
+                        for (index, setter) in builder.setters.iter().enumerate() {
+                            argument_lists.push((format!("{}.Builder.setters[{}].arguments", constructor_path, index), &setter.arguments));
+                        }
+                        for (index, call) in builder.calls.iter().enumerate() {
+                            argument_lists.push((format!("{}.Builder.calls[{}].arguments", constructor_path, index), &call.arguments));
+                        }
+                    }
+                    _ => {}
+                }
+
+                for (arguments_path, arguments) in argument_lists {
+                    for (index, arg) in arguments.iter().enumerate() {
+                        self.validate_argument(
+                            arg,
+                            &type_parameters,
+                            &format!("{}[{}]", arguments_path, index),
+                            pack_man,
+                            issues,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn validate_argument(
+        &self,
+        arg: &Argument,
+        type_parameters: &[String],
+        path: &str,
+        pack_man: &PackageManager,
+        issues: &mut Vec<ValidationIssue>,
+    ) {
+        self.validate_argument_type(&arg.arg_type, type_parameters, &format!("{}.type", path), pack_man, issues);
+
+        if let Some(trait_name) = &arg.trait_object {
+            if !arg.wrapping.iter().any(|wrapping| wrapping.is_pointer()) {
+                issues.push(ValidationIssue::new(
+                    format!("{}.trait_object", path),
+                    format!("Trait object 'dyn {}' requires a Box, Arc, ArcMutex or Rc wrapping.", trait_name),
+                ));
+            }
+        }
+
+        if arg.construction.is_none() && !arg.arg_type.is_enum_variant() {
+            issues.push(ValidationIssue::new(format!("{}.construction", path), "Missing construction.".to_string()));
+        }
+
+        if let Some(ArgumentConstruction::Expression(expression)) = &arg.construction {
+            if let Err(err) = parse_expression(expression) {
+                issues.push(ValidationIssue::new(format!("{}.construction.Expression", path), err.to_string()));
+            }
+        }
+
+        if let Some(ArgumentConstruction::Constructor(constructor_name)) = &arg.construction {
+            // References are constructed like the referenced type.
+            let mut arg_type = arg.arg_type.as_ref();
+            while let ArgumentType::Reference { element, .. } = arg_type {
+                arg_type = element.as_ref();
+            }
+
+            // Constructors of generic arguments can only be checked once the generic is resolved in a flow.
+            match arg_type {
+                ArgumentType::Type { name, .. } => {
+                    if let Some(t) = self.resolve_type(name, pack_man) {
+                        if !t.constructors.contains_key(constructor_name) {
+                            issues.push(ValidationIssue::new(
+                                format!("{}.construction.Constructor", path),
+                                format!("Type '{}' has no constructor '{}'.", name, constructor_name),
+                            ));
+                        }
+                    }
+                }
+
+                ArgumentType::Tuple { .. }
+                | ArgumentType::Array { .. }
+                | ArgumentType::Slice { .. }
+                | ArgumentType::Option { .. } if ArgumentType::structural_constructor(constructor_name).is_none() => {
+                    issues.push(ValidationIssue::new(
+                        format!("{}.construction.Constructor", path),
+                        format!("Constructor '{}' is not available, use 'Json' or 'Default'.", constructor_name),
+                    ));
+                }
+
+                ArgumentType::EnumVariant { .. } => {
+                    issues.push(ValidationIssue::new(
+                        format!("{}.construction.Constructor", path),
+                        "Enum variants are not constructed, remove the construction.".to_string(),
+                    ));
+                }
+
+                _ => {}
+            }
+        }
+    }
+
+    fn validate_argument_type(
+        &self,
+        arg_type: &ArgumentType,
+        type_parameters: &[String],
+        path: &str,
+        pack_man: &PackageManager,
+        issues: &mut Vec<ValidationIssue>,
+    ) {
+        let (variant, arg_type_parameters) = match arg_type {
+            ArgumentType::Type { name, arg_type_parameters } => {
+                if self.resolve_type(name, pack_man).is_none() {
+                    issues.push(ValidationIssue::new(
+                        format!("{}.Type.name", path),
+                        format!("Unknown type '{}'.", name),
+                    ));
+                }
+                ("Type", arg_type_parameters)
+            }
+
+            ArgumentType::Generic { name, arg_type_parameters } => {
+                if !type_parameters.contains(name) {
+                    issues.push(ValidationIssue::new(
+                        format!("{}.Generic.name", path),
+                        format!("Generic '{}' is not a type parameter of the type.", name),
+                    ));
+                }
+                ("Generic", arg_type_parameters)
+            }
+
+            ArgumentType::EnumVariant { name, arg_type_parameters, .. } => {
+                if self.resolve_type(name, pack_man).is_none() {
+                    issues.push(ValidationIssue::new(
+                        format!("{}.EnumVariant.name", path),
+                        format!("Unknown type '{}'.", name),
+                    ));
+                }
+                ("EnumVariant", arg_type_parameters)
+            }
+
+            ArgumentType::Tuple { elements } => {
+                for (index, element) in elements.iter().enumerate() {
+                    self.validate_argument_type(
+                        element,
+                        type_parameters,
+                        &format!("{}.Tuple.elements[{}]", path, index),
+                        pack_man,
+                        issues,
+                    );
+                }
+                return;
+            }
+
+            ArgumentType::Array { element, .. } => {
+                self.validate_argument_type(element, type_parameters, &format!("{}.Array.element", path), pack_man, issues);
+                return;
+            }
+
+            ArgumentType::Slice { element } => {
+                issues.push(ValidationIssue::new(
+                    format!("{}.Slice", path),
+                    "Slices can only be passed by reference, wrap the slice in a 'Reference'.".to_string(),
+                ));
+                self.validate_argument_type(element, type_parameters, &format!("{}.Slice.element", path), pack_man, issues);
+                return;
+            }
+
+            ArgumentType::Reference { element, .. } => {
+                let element_path = format!("{}.Reference.element", path);
+                match element.as_ref() {
+                    ArgumentType::Slice { element } => {
+                        self.validate_argument_type(element, type_parameters, &format!("{}.Slice.element", element_path), pack_man, issues);
+                    }
+                    element => self.validate_argument_type(element, type_parameters, &element_path, pack_man, issues),
+                }
+                return;
+            }
+
+            ArgumentType::Option { element } => {
+                self.validate_argument_type(element, type_parameters, &format!("{}.Option.element", path), pack_man, issues);
+                return;
+            }
+        };
+
+        if let Some(params) = arg_type_parameters {
+            for (index, param) in params.iter().enumerate() {
+                self.validate_argument_type(
+                    param,
+                    type_parameters,
+                    &format!("{}.{}.type_parameters[{}]", path, variant, index),
+                    pack_man,
+                    issues,
+                );
+            }
+        }
+    }
+}
+
+/// Code without whitespace, token streams print with arbitrary spacing.
+#[cfg(test)]
+pub(crate) fn compact_code(code: &str) -> String {
+    code.replace(char::is_whitespace, "")
+}
+
+/// Emits the compacted code of an object `node` of type `type_name`, built by its constructor `constructor_name`.
+#[cfg(test)]
+fn emit_test_code(
+    package_json: &str,
+    type_name: &str,
+    constructor_name: &str,
+    type_parameters: &HashMap<String, String>,
+    argument_overrides: HashMap<String, ArgumentConstruction>,
+) -> Result<String, Error> {
+    let mut pm = PackageManager::new();
+    pm.add_package(serde_json::from_str(package_json)?);
+    let node_type = pm.get_type(type_name).ok_or_else(|| Error::msg("type not found."))?;
+    let constructor = node_type.constructors.get(constructor_name).ok_or_else(|| Error::msg("constructor not found."))?;
+
+    let type_arguments = node_type
+        .type_parameters
+        .iter()
+        .flatten()
+        .map(|type_parameter| parse_code(&type_parameters[type_parameter], "type"))
+        .collect::<Result<Vec<syn::Type>, Error>>()?;
+    let obj = ObjectDescription {
+        type_name: type_name.to_string(),
+        object_type: emit_generic_type(emit_type_path(type_name)?, &type_arguments),
+        name: "node".to_string(),
+        is_mutable: false,
+        argument_overrides,
+    };
+
+    let code = constructor.emit_code_template(&obj, type_parameters, &pm, &Namespace::new(), &JsonDataSource::Runtime)?;
+    Ok(compact_code(&code.to_string()))
+}
 
 #[test]
 fn test() {
@@ -659,9 +1509,10 @@ fn test() {
                 "inputs": null,
                 "outputs": null,
                 "type_parameters": ["U", "T"],
-                "constructors": 
+                "constructors": {
                     "New":{"NewWithObserverAndContext": {}}
-            }
+                }
+              }
             },
             "modules": {}
           }
@@ -669,7 +1520,16 @@ fn test() {
       }
     "#;
 
-    let package_json_2 = r#"
+    let mut type_params = HashMap::new();
+    type_params.insert("U".to_string(), "i32".to_string());
+    type_params.insert("T".to_string(), "i32".to_string());
+    let code = emit_test_code(package_json, "my_crate::MyType", "New", &type_params, HashMap::new()).expect("");
+    assert_eq!(code, compact_code("let node = my_crate::MyType::<i32, i32>::new(change_observer.clone(), context.clone());"));
+}
+
+#[test]
+fn test_validate() {
+    let package_json = r#"
     {
         "name":"my_package",
         "version":"1.0.0",
@@ -677,281 +1537,393 @@ fn test() {
            "my_crate":{
               "types":{
                  "ValueNode":{
-                    "type_parameters":[
-                       "T"
-                    ],
+                    "type_parameters":["T"],
                     "constructors":{
-                       "New": 
-                       {"NewWithArbitraryArgs":
-                       { "arguments":
-                       [
-                          {
-                             "arg_type":{
-                                "Generic":{
-                                   "name":"T"
+                       "New":{
+                          "NewWithArbitraryArgs":{
+                             "arguments":[
+                                {
+                                   "type":{"Generic":{"name":"U"}},
+                                   "name":"value",
+                                   "passing":"Move",
+                                   "construction":{"Constructor":"Json"}
+                                },
+                                {
+                                   "type":{"Type":{"name":"my_crate::Missing"}},
+                                   "name":"missing",
+                                   "passing":"Move",
+                                   "construction":{"Constructor":"Json"}
+                                },
+                                {
+                                   "type":{"Type":{"name":"i32"}},
+                                   "name":"number",
+                                   "passing":"Move",
+                                   "construction":{"Constructor":"Unknown"}
                                 }
-                             },
-                             "name":"value",
-                             "passing":"MutableReference",
-                             "construction":{"ExistingObject":[]}
+                             ]
                           }
-                       ]
-                        }
-                    }
+                       }
                     }
-                 },
+                 }
+              },
+              "modules":{}
+           }
+        }
+    }
+    "#;
+
+    let package: Package = serde_json::from_str(package_json).expect("wrong format.");
+    let issues = package.validate(&PackageManager::new());
+    let paths: Vec<&str> = issues.iter().map(|issue| issue.path.as_str()).collect();
+
+    let args_path = "$.crates.my_crate.types.ValueNode.constructors.New.NewWithArbitraryArgs.arguments";
+    assert_eq!(
+        paths,
+        vec![
+            format!("{}[0].type.Generic.name", args_path),
+            format!("{}[1].type.Type.name", args_path),
+            format!("{}[2].construction.Constructor", args_path),
+        ]
+    );
+
+    let package_json = package_json.replace("\"my_crate\":{", "\"my-crate\":{").replace("\"ValueNode\"", "\"Value.Node\"");
+    let package: Package = serde_json::from_str(&package_json).expect("wrong format.");
+    let issues = package.validate(&PackageManager::new());
+    assert_eq!(issues[0].path, "$.crates['my-crate'].types['Value.Node'].constructors.New.NewWithArbitraryArgs.arguments[0].type.Generic.name");
+    assert_eq!(json_path_child("$", "it's"), "$['it\\'s']");
+}
+
+#[test]
+fn test_validate_flowrs_std() {
+    let mut pm = PackageManager::new();
+    let package: Package = serde_json::from_str(include_str!("../flow-packages/flowrs-std.json")).expect("wrong format.");
+    pm.add_package(package.clone());
+
+    let issues = package.validate(&pm);
+    assert!(issues.is_empty(), "{}", issues.iter().map(|issue| issue.to_string()).collect::<Vec<String>>().join("\n"));
+}
 
-                 "ImageNode":{
-                    "type_parameters":[
-                       "T"
-                    ],
+#[test]
+fn test_structural_argument_types() {
+    let package_json = r#"
+    {
+        "name":"my_package",
+        "version":"1.0.0",
+        "crates":{
+           "my_crate":{
+              "types":{
+                 "Mode":{
+                    "constructors":{}
+                 },
+                 "MyNode":{
+                    "type_parameters":["T"],
                     "constructors":{
-                        "New": {
-                       "NewWithArbitraryArgs":[
-                          {
-                             "arg_type":{
-                                "Type":{
-                                   "name":"my_crate::ImageType",
-                                   "type_parameters":[{
-                                    "Generic":{
-                                        "name":"T"
-                                        }
-                                    }
-                                    ]
-                                
+                       "New":{
+                          "NewWithArbitraryArgs":{
+                             "arguments":[
+                                {
+                                   "type":{"Tuple":{"elements":[{"Type":{"name":"u32"}}, {"Generic":{"name":"T"}}]}},
+                                   "name":"size",
+                                   "passing":"Move",
+                                   "construction":{"Constructor":"Json"}
+                                },
+                                {
+                                   "type":{"Option":{"element":{"Type":{"name":"i32"}}}},
+                                   "name":"limit",
+                                   "passing":"Move",
+                                   "construction":{"Constructor":"Default"}
+                                },
+                                {
+                                   "type":{"Reference":{"element":{"Slice":{"element":{"Type":{"name":"u8"}}}}}},
+                                   "name":"bytes",
+                                   "passing":"Move",
+                                   "construction":{"Constructor":"Json"}
+                                },
+                                {
+                                   "type":{"EnumVariant":{"name":"my_crate::Mode","variant":"Fast"}},
+                                   "name":"mode",
+                                   "passing":"Move"
                                 }
-                             },
-                             "name":"image",
-                             "passing":"MutableReference",
-                             "construction":{"Constructor": "FromJson"}
+                             ]
                           }
-                       ]
-                    }
+                       }
                     }
-                 },
-                 "ValueType":{
-                    "constructors": {"FromJson": "FromJson"}
-                 },
-
-
-                 "ImageType":{
-                    "constructors": {"FromJson": "FromJson"},
-                    "type_parameters":[
-                       "T"
-                    ]
                  }
               },
-              "modules":{
-                 
-              }
+              "modules":{}
            }
         }
-     }
+    }
     "#;
 
-    let package_json_3 = r#"
-    
+    let mut type_params = HashMap::new();
+    type_params.insert("T".to_string(), "f32".to_string());
+
+    let code = emit_test_code(package_json, "my_crate::MyNode", "New", &type_params, HashMap::new()).expect("code generation failed.");
+    assert!(code.contains(&compact_code("let node_size: (u32,f32,) = serde_json::from_value(data[\"node\"][\"size\"].clone())")));
+    assert!(code.contains(&compact_code("let node_limit:Option<i32> = Default::default();")));
+    assert!(code.contains(&compact_code("let node_bytes: Vec<u8> = serde_json::from_value")));
+    assert!(code.contains(&compact_code("let node_mode: my_crate::Mode = my_crate::Mode::Fast;")));
+    assert!(code.contains(&compact_code("my_crate::MyNode::<f32>::new(node_size, node_limit, &node_bytes, node_mode);")));
+
+    let package: Package = serde_json::from_str(package_json).expect("wrong format.");
+    let mut pm = PackageManager::new();
+    pm.add_package(package.clone());
+    assert!(package.validate(&pm).is_empty());
+
+    // Slices passed by value and constructed enum variants are rejected.
+    let invalid_package_json = package_json
+        .replace(r#"{"Reference":{"element":{"Slice""#, r#"{"Option":{"element":{"Slice""#)
+        .replace(r#""passing":"Move"
+                                }"#, r#""passing":"Move",
+                                   "construction":{"Constructor":"Default"}
+                                }"#);
+    let package: Package = serde_json::from_str(&invalid_package_json).expect("wrong format.");
+    let args_path = "$.crates.my_crate.types.MyNode.constructors.New.NewWithArbitraryArgs.arguments";
+    let paths: Vec<String> = package.validate(&pm).into_iter().map(|issue| issue.path).collect();
+    assert_eq!(
+        paths,
+        vec![format!("{}[2].type.Option.element.Slice", args_path), format!("{}[3].construction.Constructor", args_path)]
+    );
+    assert!(emit_test_code(&invalid_package_json, "my_crate::MyNode", "New", &type_params, HashMap::new()).is_err());
+}
+
+#[test]
+fn test_literal_and_expression_arguments() {
+    let package_json = r#"
     {
-        "name":"flowrs-std",
+        "name":"my_package",
         "version":"1.0.0",
         "crates":{
-           "flowrs_std":{
+           "my_crate":{
               "types":{
-                 
-              },
-              "modules":{
-                 "nodes":{
-                    "types":{
-                       
-                    },
-                    "modules":{
-                       "debug":{
-                          "modules":{
-                             
-                          },
-                          "types":{
-                             "DebugNode":{
-                                "inputs":[
-                                   "input"
-                                ],
-                                "outputs":[
-                                   "output"
-                                ],
-                                "type_parameters":[
-                                   "I"
-                                ],
-                                "constructors":{
-                                   "New":{"NewWithObserver": {}}
-                                }
-                             }
-                          }
-                       },
-                       "value":{
-                          "modules":{
-                             
-                          },
-                          "types":{
-                             "ValueType":{
-                                "constructors":{
-                                   "Json":"FromJson"
-                                }
-                             },
-                             "ValueNode":{
-                                "inputs":[
-                                   
-                                ],
-                                "outputs":[
-                                   "output"
-                                ],
-                                "type_parameters":[
-                                   "I"
-                                ],
-                                "constructors":{
-                                   "New":{
-                                      "NewWithArbitraryArgs":{
-                                         "arguments":[
-                                            {
-                                               "type":{
-                                                  "Generic":{
-                                                     "name":"I"
-                                                  }
-                                               },
-                                               "name":"value",
-                                               "passing":"Move",
-                                               "construction":{
-                                                  "Constructor":"Json"
-                                               }
-                                            },
-                                            {
-                                               "type":{
-                                                  "Type":{
-                                                     "name":"()"
-                                                  }
-                                               },
-                                               "name":"change_observer",
-                                               "passing":"Clone",
-                                               "construction":{
-                                                  "ExistingObject":[
-                                                     
-                                                  ]
-                                               }
-                                            }
-                                         ]
-                                      }
-                                   }
+                 "MyNode":{
+                    "constructors":{
+                       "New":{
+                          "NewWithArbitraryArgs":{
+                             "arguments":[
+                                {
+                                   "type":{"Type":{"name":"u32"}},
+                                   "name":"count",
+                                   "passing":"Move",
+                                   "construction":{"Literal":42}
+                                },
+                                {
+                                   "type":{"Type":{"name":"String"}},
+                                   "name":"label",
+                                   "passing":"Move",
+                                   "construction":{"Literal":"say \"hi\""}
+                                },
+                                {
+                                   "type":{"Type":{"name":"std::time::Duration"}},
+                                   "name":"timeout",
+                                   "passing":"Move",
+                                   "construction":{"Expression":"std::time::Duration::from_secs(1)"}
                                 }
-                             }
+                             ]
                           }
                        }
                     }
                  }
-              }
+              },
+              "modules":{}
            }
         }
-     }
-    
-    
+    }
     "#;
 
-    /*
-    let arg1 = Argument {
-        arg_type: ArgumentType::simple_type("my_crate::IntType"),
-        name: "my_argument".to_string(),
-        passing: ArgumentPassing::Move,
-        existing_object: false,
+    let emit = |argument_overrides: HashMap<String, ArgumentConstruction>| {
+        emit_test_code(package_json, "my_crate::MyNode", "New", &HashMap::new(), argument_overrides)
     };
 
-    let args = vec![arg1];
+    let code = emit(HashMap::new()).expect("code generation failed.");
+    assert!(code.contains(&compact_code("let node_count: u32 = 42u32;")));
+    assert!(code.contains(&compact_code("let node_label: String = String::from(\"say \\\"hi\\\"\");")));
+    assert!(code.contains(&compact_code(
+        "let node_timeout: std::time::Duration = { const VALUE: std::time::Duration = std::time::Duration::from_secs(1); VALUE };"
+    )));
 
-    let my_type = Type {
-        inputs: Option::None,
-        outputs: Option::None,
-        type_parameters: Option::None,
-        constructor: DynamicConstructor::NewWithArbitraryArgs(args),
-    };
-
-    let int_type = Type {
-        inputs: Option::None,
-        outputs: Option::None,
-        type_parameters: Option::None,
-        constructor: DynamicConstructor::New,
-    };
-     */
+    let overrides = |name: &str, construction: ArgumentConstruction| HashMap::from([(name.to_string(), construction)]);
+    let code = emit(overrides("count", ArgumentConstruction::Literal(serde_json::json!(7)))).expect("code generation failed.");
+    assert!(code.contains(&compact_code("let node_count: u32 = 7u32;")));
 
-    /*
-    let mut types = HashMap::<String, Type>::new();
-    types.insert("MyType".into(), my_type);
-    types.insert("IntType".into(), int_type);
-
-    let mut modules = HashMap::<String, Module>::new();
-
-    let my_crate = Crate{ types: types, modules: modules};
-
-    let mut crates = HashMap::<String, Crate>::new();
-    crates.insert("my_crate".into(), my_crate);
+    for literal in [serde_json::json!(-1), serde_json::json!(1.5), serde_json::json!("7")] {
+        assert!(emit(overrides("count", ArgumentConstruction::Literal(literal))).is_err());
+    }
+    assert!(emit(overrides("label", ArgumentConstruction::Literal(serde_json::json!(42)))).is_err());
 
-    let p = Package { name: "my_package".into(), version: "1.0.0".into(), crates: crates};
+    for expression in ["1; drop(x)", "{ std::process::Command::new(\"sh\").spawn(); 1 }", "unsafe { f() }", "(|| 1)()", "g(vec![1])"] {
+        assert!(emit(overrides("timeout", ArgumentConstruction::Expression(expression.to_string()))).is_err());
+    }
+}
 
-    let json = serde_json::to_string(&p).unwrap();
-    println!("{}", json);
-    */
+#[test]
+fn test_builder_constructor() {
+    let package_json = r#"
+    {
+        "name":"my_package",
+        "version":"1.0.0",
+        "crates":{
+           "my_crate":{
+              "types":{
+                 "MyNode":{
+                    "constructors":{
+                       "Build":{
+                          "Builder":{
+                             "setters":[
+                                {
+                                   "function_name":"width",
+                                   "arguments":[
+                                      {
+                                         "type":{"Type":{"name":"u32"}},
+                                         "name":"value",
+                                         "passing":"Move",
+                                         "construction":{"Literal":640}
+                                      }
+                                   ]
+                                },
+                                {
+                                   "function_name":"height",
+                                   "arguments":[
+                                      {
+                                         "type":{"Type":{"name":"u32"}},
+                                         "name":"value",
+                                         "passing":"Move",
+                                         "construction":{"Constructor":"Json"}
+                                      }
+                                   ]
+                                }
+                             ],
+                             "calls":[
+                                {"function_name":"start"}
+                             ]
+                          }
+                       }
+                    }
+                 }
+              },
+              "modules":{}
+           }
+        }
+    }
+    "#;
 
-    let package_1: Package = serde_json::from_str(&package_json).expect("wrong format.");
-    let mut pm_1 = PackageManager::new();
-    pm_1.add_package(package_1);
-    let t_1 = pm_1.get_type("my_crate::MyType").expect("msg");
-    let c_1 = t_1.constructors.get("New").expect("");
-    let mut type_params_1 = HashMap::new();
-    type_params_1.insert("U".to_string(), "i32".to_string());
-    type_params_1.insert("T".to_string(), "i32".to_string());
-    let mut ns_1 = Namespace::new();
+    let argument_overrides = HashMap::from([("width.value".to_string(), ArgumentConstruction::Literal(serde_json::json!(800)))]);
+    let code = emit_test_code(package_json, "my_crate::MyNode", "Build", &HashMap::new(), argument_overrides).expect("code generation failed.");
 
-    let obj_1 = ObjectDescription {
-        type_name: "my_crate::MyType".to_string(),
-        type_parameter_part: "".to_string(),
-        name: "value".to_string(),
-        is_mutable: false,
-    };
-    println!("CODE: {}", c_1.emit_code_template(&obj_1, &type_params_1, &pm_1, &ns_1).expect(""));    
+    assert!(code.contains(&compact_code("let node_width_value: u32 = 800u32;")));
+    assert!(code.contains(&compact_code("data[\"node\"][\"height\"][\"value\"]")));
+    assert!(code.contains(&compact_code("let mut node = my_crate::MyNode::builder().width(node_width_value).height(node_height_value).build();")));
+    assert!(code.contains(&compact_code("node.start();")));
+}
 
-    /*
-    let a = Argument::new_change_observer_arg();
-    let json = serde_json::to_string(&a).unwrap();
-    println!("ARG: {}", json);
+#[test]
+fn test_argument_wrapping() {
+    let package_json = r#"
+    {
+        "name":"my_package",
+        "version":"1.0.0",
+        "crates":{
+           "my_crate":{
+              "types":{
+                 "MyFilter":{
+                    "constructors":{"Default":"FromDefault"}
+                 },
+                 "MyNode":{
+                    "constructors":{
+                       "New":{
+                          "NewWithArbitraryArgs":{
+                             "arguments":[
+                                {
+                                   "type":{"Type":{"name":"my_crate::MyFilter"}},
+                                   "name":"filter",
+                                   "passing":"Move",
+                                   "construction":{"Constructor":"Default"},
+                                   "wrapping":["Box", "Some"],
+                                   "trait_object":"my_crate::Filter"
+                                },
+                                {
+                                   "type":{"Type":{"name":"u32"}},
+                                   "name":"state",
+                                   "passing":"Move",
+                                   "construction":{"Literal":0},
+                                   "wrapping":["ArcMutex"]
+                                }
+                             ]
+                          }
+                       }
+                    }
+                 }
+              },
+              "modules":{}
+           }
+        }
+    }
+    "#;
 
-    let package: Package = serde_json::from_str(&package_json_3).expect("wrong format.");
+    let code = emit_test_code(package_json, "my_crate::MyNode", "New", &HashMap::new(), HashMap::new()).expect("code generation failed.");
+    assert!(code.contains(&compact_code("my_crate::MyNode::new(Some(Box::new(node_filter) as Box<dyn my_crate::Filter>), std::sync::Arc::new(std::sync::Mutex::new(node_state)));")));
 
+    let package: Package = serde_json::from_str(package_json).expect("wrong format.");
+    let mut pm = PackageManager::new();
+    pm.add_package(package.clone());
+    assert!(package.validate(&pm).is_empty());
+}
 
-    let mut type_params = HashMap::new();
-    type_params.insert("T".to_string(), "i32".to_string());
+#[test]
+fn test_malformed_descriptions() {
+    let package_json = r#"
+    {
+        "name":"my_package",
+        "version":"1.0.0",
+        "crates":{
+           "my_crate":{
+              "types":{
+                 "MyNode":{
+                    "constructors":{
+                       "New":{"New":{"function_name":"new()"}},
+                       "BadArgument":{
+                          "NewWithArbitraryArgs":{
+                             "arguments":[
+                                {
+                                   "type":{"Type":{"name":"u32"}},
+                                   "name":"my count",
+                                   "passing":"Move",
+                                   "construction":{"Literal":1}
+                                }
+                             ]
+                          }
+                       }
+                    }
+                 }
+              },
+              "modules":{}
+           }
+        }
+    }
+    "#;
 
-    let mut pm = PackageManager::new();
-    pm.add_package(package);
+    for constructor_name in ["New", "BadArgument"] {
+        assert!(emit_test_code(package_json, "my_crate::MyNode", constructor_name, &HashMap::new(), HashMap::new()).is_err());
+    }
 
-    let t = pm.get_type("my_crate::ImageNode");
+    assert!(emit_type_path("my_crate::My Node").is_err());
+    assert!(emit_type_path("my_crate::MyNode<i32>").is_err());
 
     let obj = ObjectDescription {
-        type_name: "my_crate::ImageNode".to_string(),
-        type_parameters: type_params.clone(),
-        name: "node1".to_string(),
+        type_name: "my_crate::MyNode".to_string(),
+        object_type: parse_quote!(my_crate::MyNode),
+        name: "node".to_string(),
         is_mutable: false,
+        argument_overrides: HashMap::new(),
     };
-
-    let mut ns = Namespace::new();
-    //ns.add_part("super");
-
-    let arg = Argument::new_context_arg();
-    println!("JSON: {}", serde_json::to_string(&arg).expect(""));
-
-    println!("TEST");
-
-    if let Some(ty) = t {
-        println!(
-            "Code: {}",
-            ty.constructors.get("New".into()).expect("Constructor should be there.")
-                .emit_code_template(&obj, &pm, &ns)
-                .expect("Did not work!")
-        );
-    } */
-     
+    let code = Constructor::FromDefault
+        .emit_code_template(&obj, &HashMap::new(), &PackageManager::new(), &Namespace::new(), &JsonDataSource::Runtime)
+        .expect("code generation failed.");
+    assert!(syn::parse2::<syn::Stmt>(code).is_ok());
+
+    let obj = ObjectDescription { name: "my node".to_string(), ..obj };
+    assert!(Constructor::FromDefault
+        .emit_code_template(&obj, &HashMap::new(), &PackageManager::new(), &Namespace::new(), &JsonDataSource::Runtime)
+        .is_err());
 }
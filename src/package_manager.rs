@@ -1,16 +1,186 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-use crate::package::{Crate, Constructor, Package, Type};
+use anyhow::{Error, Result};
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::package::{Crate, Module, Package, Type, ValidationIssue};
 
 pub struct PackageManager {
     packages: HashMap<String, Package>,
+    packages_folder: Option<String>,
+    /// Load status per package file name.
+    package_files: HashMap<String, PackageFileStatus>,
+    /// Path of the file each package was loaded from or saved to.
+    package_paths: HashMap<String, PathBuf>,
+}
+
+/// Load status of a package file in the package folder.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PackageFileStatus {
+    pub file_name: String,
+    pub package_name: String,
+    #[serde(skip)]
+    modified: Option<SystemTime>,
+    /// Error of the last load attempt. If set, the previously loaded version of the package (if any) is still in use.
+    pub error: Option<String>,
+    /// Problems found by validating the loaded package.
+    #[serde(default)]
+    pub validation_issues: Vec<ValidationIssue>,
+}
+
+/// Package folder and the modification times of the package files the manager knows about.
+#[derive(Clone)]
+pub struct PackageFolderState {
+    folder: String,
+    modified: HashMap<String, Option<SystemTime>>,
+}
+
+/// Package files read from the package folder, see `PackageManager::apply_package_folder`.
+pub struct PackageFolderContents {
+    state: PackageFolderState,
+    /// All package files in the folder.
+    file_names: HashSet<String>,
+    /// Files that are new or were modified since the state was taken.
+    changed_files: Vec<PackageFileContents>,
+}
+
+struct PackageFileContents {
+    file_name: String,
+    modified: Option<SystemTime>,
+    package: Result<Package, Error>,
+}
+
+impl PackageFolderState {
+    /// Reads all package files that are new or changed. Returns None if the folder cannot be read.
+    pub fn read(self) -> Option<PackageFolderContents> {
+        let entries = match fs::read_dir(&self.folder) {
+            Ok(es) => es,
+            Err(e) => {
+                eprintln!(
+                    "-> Failed to read package folder '{}'. Reason: {}",
+                    self.folder,
+                    e
+                );
+                return Option::None;
+            }
+        };
+
+        let mut file_names = HashSet::new();
+        let mut changed_files = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let file_name = match path.file_name() {
+                Some(file_name) => file_name.to_string_lossy().to_string(),
+                None => continue,
+            };
+            file_names.insert(file_name.clone());
+
+            let modified = entry.metadata().and_then(|m| m.modified()).ok();
+            if modified.is_some() && self.modified.get(&file_name) == Some(&modified) {
+                continue;
+            }
+
+            let package = fs::read_to_string(&path)
+                .map_err(Error::from)
+                .and_then(|contents| serde_json::from_str::<Package>(&contents).map_err(Error::from));
+            changed_files.push(PackageFileContents { file_name, modified, package });
+        }
+
+        Some(PackageFolderContents { state: self, file_names, changed_files })
+    }
+}
+
+/// Flattened view of a type, as listed in the type index.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TypeDescription {
+    pub fqn: String,
+    pub package: String,
+    pub version: String,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    pub type_parameters: Vec<String>,
+    pub constructors: Vec<String>,
+    pub documentation: Option<String>,
+}
+
+impl TypeDescription {
+    fn new(fqn: String, package: &Package, t: &Type) -> Self {
+        let mut constructors: Vec<String> = t.constructors.keys().cloned().collect();
+        constructors.sort();
+
+        Self {
+            fqn,
+            package: package.name.clone(),
+            version: package.version.clone(),
+            inputs: t.inputs.clone().unwrap_or_default(),
+            outputs: t.outputs.clone().unwrap_or_default(),
+            type_parameters: t.type_parameters.clone().unwrap_or_default(),
+            constructors,
+            documentation: t.documentation.clone(),
+        }
+    }
+}
+
+/// Filter for searching the type index.
+/// `q` is matched fuzzily against the fully qualified type name.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct TypeQuery {
+    pub q: Option<String>,
+    pub package: Option<String>,
+    pub has_inputs: Option<bool>,
+    pub has_outputs: Option<bool>,
+    pub has_type_parameters: Option<bool>,
+}
+
+impl TypeQuery {
+    fn matches_filters(&self, td: &TypeDescription) -> bool {
+        if let Some(package) = &self.package {
+            if &td.package != package {
+                return false;
+            }
+        }
+        if let Some(has_inputs) = self.has_inputs {
+            if td.inputs.is_empty() == has_inputs {
+                return false;
+            }
+        }
+        if let Some(has_outputs) = self.has_outputs {
+            if td.outputs.is_empty() == has_outputs {
+                return false;
+            }
+        }
+        if let Some(has_type_parameters) = self.has_type_parameters {
+            if td.type_parameters.is_empty() == has_type_parameters {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Default for PackageManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PackageManager {
     pub fn new() -> Self {
         let mut pm = Self {
             packages: HashMap::new(),
+            packages_folder: Option::None,
+            package_files: HashMap::new(),
+            package_paths: HashMap::new(),
         };
 
         pm.add_built_in_package();
@@ -19,53 +189,170 @@ impl PackageManager {
     }
 
     pub fn new_from_folder(directory_path: &str) -> Self {
-        let mut packages: HashMap<String, Package> = HashMap::new();
-
-        let entries = fs::read_dir(directory_path);
-        match entries {
-            Ok(es) => {
-                for entry in es {
-                    if let Ok(entry) = entry {
-                        if let Some(extension) = entry.path().extension() {
-                            if extension == "json" {
-                                if let Some(file_name) = entry.path().file_stem() {
-                                    let package_name = file_name.to_string_lossy().to_string();
-
-                                    if let Ok(contents) = fs::read_to_string(entry.path()) {
-                                        let package = serde_json::from_str::<Package>(&contents);
-                                        match package {
-                                            Ok(p) => {
-                                                packages.insert(package_name.clone(), p);
-                                            }
-                                            Err(e) => {
-                                                eprintln!(
-                                                    "-> Failed to deserialize package: {}. Reason: {}",
-                                                    package_name,
-                                                    e.to_string()
-                                                );
-                                            }
-                                        }
-                                    }
-                                }
+        let mut pm = PackageManager {
+            packages: HashMap::new(),
+            packages_folder: Some(directory_path.to_string()),
+            package_files: HashMap::new(),
+            package_paths: HashMap::new(),
+        };
+        pm.add_built_in_package();
+        pm.reload_from_folder();
+
+        pm
+    }
+
+    /// Re-reads all package files in the package folder that changed since the last call.
+    /// A package whose file fails to load keeps its previous version, the error is kept in the load status.
+    /// Packages whose files were deleted are removed.
+    /// Returns the names of all packages that were (re-)loaded or removed.
+    pub fn reload_from_folder(&mut self) -> Vec<String> {
+        match self.get_package_folder_state().and_then(|state| state.read()) {
+            Some(contents) => self.apply_package_folder(contents),
+            None => Vec::new(),
+        }
+    }
+
+    /// Package folder and the modification times of its known files. None if the manager has no folder.
+    /// Read the changed files with `PackageFolderState::read` and apply them with `apply_package_folder`,
+    /// so callers sharing the manager do not need to hold a lock while files are read.
+    pub fn get_package_folder_state(&self) -> Option<PackageFolderState> {
+        self.packages_folder.as_ref().map(|folder| PackageFolderState {
+            folder: folder.clone(),
+            modified: self
+                .package_files
+                .iter()
+                .map(|(file_name, status)| (file_name.clone(), status.modified))
+                .collect(),
+        })
+    }
+
+    /// Applies package files read by `PackageFolderState::read`, see `reload_from_folder`.
+    /// Files that were saved or removed through the manager since the state was taken are left alone.
+    pub fn apply_package_folder(&mut self, contents: PackageFolderContents) -> Vec<String> {
+        let mut changed_packages = Vec::new();
+
+        let is_unchanged = |package_files: &HashMap<String, PackageFileStatus>, file_name: &String| {
+            package_files.get(file_name).map(|status| status.modified) == contents.state.modified.get(file_name).cloned()
+        };
+
+        for file in contents.changed_files {
+            if !is_unchanged(&self.package_files, &file.file_name) {
+                continue;
+            }
+
+            let path = Path::new(&contents.state.folder).join(&file.file_name);
+            let previous_package_name = self.package_files.get(&file.file_name).map(|status| status.package_name.clone());
+
+            let (package_name, error) = match file.package {
+                Ok(p) if p.name == "built-in" => {
+                    (p.name, Some("The built-in package cannot be replaced.".to_string()))
+                }
+                Ok(p) => match self.package_paths.get(&p.name) {
+                    Some(other_path) if *other_path != path => {
+                        let error = format!("Package '{}' is already loaded from '{}'.", p.name, other_path.to_string_lossy());
+                        (p.name, Some(error))
+                    }
+                    _ => {
+                        // The file may now contain a different package than before.
+                        if let Some(previous_package_name) = previous_package_name.filter(|name| *name != p.name) {
+                            if self.package_paths.get(&previous_package_name) == Some(&path) {
+                                self.package_paths.remove(&previous_package_name);
+                                self.packages.remove(&previous_package_name);
+                                changed_packages.push(previous_package_name);
                             }
                         }
+                        let package_name = p.name.clone();
+                        self.package_paths.insert(package_name.clone(), path.clone());
+                        self.packages.insert(package_name.clone(), p);
+                        changed_packages.push(package_name.clone());
+                        (package_name, Option::None)
                     }
+                },
+                Err(e) => {
+                    eprintln!(
+                        "-> Failed to deserialize package file: {}. Reason: {}",
+                        file.file_name,
+                        e
+                    );
+                    let package_name = previous_package_name
+                        .unwrap_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().to_string());
+                    (package_name, Some(e.to_string()))
+                }
+            };
+
+            self.package_files.insert(
+                file.file_name.clone(),
+                PackageFileStatus {
+                    file_name: file.file_name,
+                    package_name,
+                    modified: file.modified,
+                    error,
+                    validation_issues: Vec::new(),
+                },
+            );
+        }
+
+        let removed_files: Vec<String> = contents
+            .state
+            .modified
+            .keys()
+            .filter(|file_name| !contents.file_names.contains(*file_name) && is_unchanged(&self.package_files, file_name))
+            .cloned()
+            .collect();
+        for file_name in removed_files {
+            if let Some(status) = self.package_files.remove(&file_name) {
+                let path = Path::new(&contents.state.folder).join(&file_name);
+                if self.package_paths.get(&status.package_name) == Some(&path) {
+                    self.package_paths.remove(&status.package_name);
+                    self.packages.remove(&status.package_name);
+                    changed_packages.push(status.package_name);
                 }
-            },
-            Err(e) => {
-                eprintln!(
-                    "-> Failed to read package folder '{}'. Reason: {}",
-                    directory_path,
-                    e.to_string()
-                );
             }
         }
 
-        
-        let mut pm = PackageManager { packages: packages };
-        pm.add_built_in_package();
+        // A changed package can resolve or introduce issues in other packages, so check all of them again.
+        if !changed_packages.is_empty() {
+            self.validate_package_files();
+        }
 
-        pm
+        changed_packages
+    }
+
+    fn validate_package_files(&mut self) {
+        let file_names: Vec<String> = self.package_files.keys().cloned().collect();
+        for file_name in file_names {
+            let package_name = self.package_files[&file_name].package_name.clone();
+            let file_path = self.packages_folder.as_ref().map(|folder| Path::new(folder).join(&file_name));
+            let is_source = file_path.is_some() && file_path.as_ref() == self.package_paths.get(&package_name);
+            let issues = match self.packages.get(&package_name) {
+                Some(package) if is_source => package.validate(self),
+                _ => Vec::new(),
+            };
+
+            for issue in &issues {
+                eprintln!("-> Package '{}': {}", package_name, issue);
+            }
+
+            if let Some(status) = self.package_files.get_mut(&file_name) {
+                status.validation_issues = issues;
+            }
+        }
+    }
+
+    /// Validates all packages. Returns the issues per package, packages without issues are omitted.
+    pub fn validate_all_packages(&self) -> HashMap<String, Vec<ValidationIssue>> {
+        self.packages
+            .iter()
+            .map(|(package_name, package)| (package_name.clone(), package.validate(self)))
+            .filter(|(_, issues)| !issues.is_empty())
+            .collect()
+    }
+
+    /// Load status of all package files, i.e., the last error per file (if any).
+    pub fn get_load_status(&self) -> Vec<PackageFileStatus> {
+        let mut status: Vec<PackageFileStatus> = self.package_files.values().cloned().collect();
+        status.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        status
     }
 
     fn add_built_in_package(&mut self) {
@@ -78,9 +365,11 @@ impl PackageManager {
 
         let mut types = HashMap::new();
         for prim in prims {
+            let mut t = Type::new_primitive_type();
+            t.data_schema = Some(self.get_primitive_data_schema(prim));
             types.insert(
                 prim.to_string(),
-                Type::new_primitive_type(),
+                t,
             );
         }
 
@@ -93,16 +382,110 @@ impl PackageManager {
         self.add_package(Package {
             name: "built-in".to_string(),
             version: "1.0.0".to_string(),
-            crates: crates,
+            crates,
+            no_std: true,
         })
     }
 
+    fn get_primitive_data_schema(&self, prim: &str) -> Value {
+        match prim {
+            "i8" => json!({"type": "integer", "minimum": i8::MIN, "maximum": i8::MAX}),
+            "i16" => json!({"type": "integer", "minimum": i16::MIN, "maximum": i16::MAX}),
+            "i32" => json!({"type": "integer", "minimum": i32::MIN, "maximum": i32::MAX}),
+            "u8" => json!({"type": "integer", "minimum": 0, "maximum": u8::MAX}),
+            "u16" => json!({"type": "integer", "minimum": 0, "maximum": u16::MAX}),
+            "u32" => json!({"type": "integer", "minimum": 0, "maximum": u32::MAX}),
+            "u64" | "u128" | "usize" => json!({"type": "integer", "minimum": 0}),
+            "f32" | "f64" => json!({"type": "number"}),
+            "bool" => json!({"type": "boolean"}),
+            "char" => json!({"type": "string", "minLength": 1, "maxLength": 1}),
+            _ => json!({"type": "integer"}),
+        }
+    }
+
     pub fn add_package(&mut self, package: Package) {
         if !self.packages.contains_key(&package.name) {
             self.packages.insert(package.name.clone(), package);
         }
     }
 
+    /// Inserts or replaces a package.
+    /// If the manager was created from a folder, the package is written first, to the file it was loaded from
+    /// or to `<folder>/<name>.json` for a new package.
+    /// The in-memory package is only replaced if writing the file succeeded.
+    pub fn save_package(&mut self, package: Package) -> Result<(), Error> {
+        if package.name == "built-in" {
+            return Err(Error::msg("The built-in package cannot be replaced."));
+        }
+
+        // The name becomes a file name, so do not allow anything that could leave the package folder.
+        if package.name.is_empty()
+            || !package.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::msg(format!("Invalid package name '{}'.", package.name)));
+        }
+
+        if let Some(file_path) = self.get_package_file_path(&package.name) {
+            let content = serde_json::to_string_pretty(&package)?;
+
+            // Write to a temporary file and rename it, so readers never see a half-written package file.
+            let tmp_file_path = file_path.with_extension("json.tmp");
+            fs::write(&tmp_file_path, content)?;
+            fs::rename(&tmp_file_path, &file_path)?;
+
+            // Remember the written file, so the next reload does not read it again.
+            let file_name = file_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            self.package_files.insert(
+                file_name.clone(),
+                PackageFileStatus {
+                    file_name,
+                    package_name: package.name.clone(),
+                    modified: fs::metadata(&file_path).and_then(|m| m.modified()).ok(),
+                    error: Option::None,
+                    validation_issues: package.validate(self),
+                },
+            );
+            self.package_paths.insert(package.name.clone(), file_path);
+        }
+
+        self.packages.insert(package.name.clone(), package);
+        Ok(())
+    }
+
+    /// Removes a package (and the file it was loaded from or saved to, if any).
+    /// Returns the removed package or None if there was no package with that name.
+    pub fn remove_package(&mut self, package_name: &str) -> Result<Option<Package>, Error> {
+        if package_name == "built-in" {
+            return Err(Error::msg("The built-in package cannot be removed."));
+        }
+
+        if !self.packages.contains_key(package_name) {
+            return Ok(Option::None);
+        }
+
+        if let Some(file_path) = self.package_paths.get(package_name).cloned() {
+            if file_path.exists() {
+                fs::remove_file(&file_path)?;
+            }
+            if let Some(file_name) = file_path.file_name() {
+                self.package_files.remove(file_name.to_string_lossy().as_ref());
+            }
+            self.package_paths.remove(package_name);
+        }
+
+        Ok(self.packages.remove(package_name))
+    }
+
+    /// Path of the file the package was loaded from or, for a new package, `<folder>/<name>.json`.
+    fn get_package_file_path(&self, package_name: &str) -> Option<PathBuf> {
+        self.packages_folder.as_ref().map(|folder| {
+            self.package_paths
+                .get(package_name)
+                .cloned()
+                .unwrap_or_else(|| Path::new(folder).join(format!("{}.json", package_name)))
+        })
+    }
+
     pub fn get_all_packages(&self) -> Vec<Package> {
         self.packages.values().cloned().collect()
     }
@@ -111,6 +494,58 @@ impl PackageManager {
         self.packages.get(package_name)
     }
 
+    pub fn get_type_index(&self) -> Vec<TypeDescription> {
+        let mut index = Vec::new();
+
+        for p in self.packages.values() {
+            for (crate_name, cr) in &p.crates {
+                // built-in types are addressed without crate prefix (see get_type).
+                let prefix = if p.name == "built-in" { "".to_string() } else { format!("{}::", crate_name) };
+
+                for (type_name, t) in &cr.types {
+                    index.push(TypeDescription::new(format!("{}{}", prefix, type_name), p, t));
+                }
+                for (module_name, module) in &cr.modules {
+                    self.add_module_to_type_index(&format!("{}{}", prefix, module_name), module, p, &mut index);
+                }
+            }
+        }
+
+        index.sort_by(|a, b| a.fqn.cmp(&b.fqn));
+        index
+    }
+
+    fn add_module_to_type_index(&self, module_path: &str, module: &Module, package: &Package, index: &mut Vec<TypeDescription>) {
+        for (type_name, t) in &module.types {
+            index.push(TypeDescription::new(format!("{}::{}", module_path, type_name), package, t));
+        }
+        for (module_name, m) in &module.modules {
+            self.add_module_to_type_index(&format!("{}::{}", module_path, module_name), m, package, index);
+        }
+    }
+
+    pub fn get_type_description(&self, type_name: &str) -> Option<TypeDescription> {
+        self.get_type_index().into_iter().find(|td| td.fqn == type_name)
+    }
+
+    pub fn find_types(&self, query: &TypeQuery) -> Vec<TypeDescription> {
+        let candidates = self.get_type_index().into_iter().filter(|td| query.matches_filters(td));
+
+        match &query.q {
+            Some(q) if !q.is_empty() => {
+                let matcher = SkimMatcherV2::default().ignore_case();
+                let mut scored: Vec<(i64, TypeDescription)> = candidates
+                    .filter_map(|td| matcher.fuzzy_match(&td.fqn, q).map(|score| (score, td)))
+                    .collect();
+
+                // best matches first, ties ordered by name.
+                scored.sort_by(|(sa, a), (sb, b)| sb.cmp(sa).then_with(|| a.fqn.cmp(&b.fqn)));
+                scored.into_iter().map(|(_, td)| td).collect()
+            }
+            _ => candidates.collect(),
+        }
+    }
+
     pub fn get_type(&self, type_name: &str) -> Option<&Type> {
         let type_ids: Vec<&str> = type_name.split("::").collect();
 
@@ -129,7 +564,7 @@ impl PackageManager {
 
         // iterate over packages and return type if available.
         // Note: We cannot handle same crate, same type, different package situations.
-        for (_, p) in &self.packages {
+        for p in self.packages.values() {
             let res = self.get_type_from_package(&type_ids, p);
             if res.is_some() {
                 return res;
@@ -139,6 +574,17 @@ impl PackageManager {
         Option::None
     }
 
+    /// Package that describes the type with the given fully qualified name.
+    pub fn get_package_of_type(&self, type_name: &str) -> Option<&Package> {
+        let type_ids: Vec<&str> = type_name.split("::").collect();
+
+        if type_ids.len() == 1 {
+            return self.packages.get("built-in").filter(|_| self.get_type(type_name).is_some());
+        }
+
+        self.packages.values().find(|p| self.get_type_from_package(&type_ids, p).is_some())
+    }
+
     pub fn get_type_from_package<'a>(
         &self,
         type_ids: &Vec<&str>,
@@ -177,3 +623,142 @@ impl PackageManager {
         Option::None
     }
 }
+
+#[test]
+fn test_type_index() {
+    let package_json = r#"
+    {
+        "name":"flowrs-std",
+        "version":"1.0.0",
+        "crates":{
+           "flowrs_std":{
+              "types":{},
+              "modules":{
+                 "nodes":{
+                    "types":{},
+                    "modules":{
+                       "debug":{
+                          "modules":{},
+                          "types":{
+                             "DebugNode":{
+                                "inputs":["input"],
+                                "outputs":["output"],
+                                "type_parameters":["I"],
+                                "constructors":{"New":{"NewWithObserver": {}}},
+                                "documentation":"Prints every input."
+                             }
+                          }
+                       }
+                    }
+                 }
+              }
+           }
+        }
+    }
+    "#;
+
+    let mut pm = PackageManager::new();
+    pm.add_package(serde_json::from_str(package_json).expect("wrong format."));
+
+    let td = pm.get_type_description("flowrs_std::nodes::debug::DebugNode").expect("type not in index.");
+    assert_eq!(td.package, "flowrs-std");
+    assert_eq!(td.constructors, vec!["New".to_string()]);
+    assert!(pm.get_type_description("i32").is_some());
+
+    let found = pm.find_types(&TypeQuery { q: Some("dbgnode".into()), ..Default::default() });
+    assert_eq!(found.len(), 1);
+
+    let with_inputs = pm.find_types(&TypeQuery { has_inputs: Some(true), ..Default::default() });
+    assert!(with_inputs.iter().all(|td| !td.inputs.is_empty()));
+    assert_eq!(with_inputs.len(), 1);
+}
+
+#[test]
+fn test_save_and_remove_package() {
+    let folder = std::env::temp_dir().join("flowrs_build_test_save_and_remove_package");
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).expect("cannot create test folder.");
+
+    let mut pm = PackageManager::new_from_folder(folder.to_str().unwrap());
+    let package = Package {
+        name: "my_package".to_string(),
+        version: "1.0.0".to_string(),
+        crates: HashMap::new(),
+        no_std: false,
+    };
+
+    pm.save_package(package).expect("cannot save package.");
+    assert!(folder.join("my_package.json").exists());
+    assert!(PackageManager::new_from_folder(folder.to_str().unwrap()).get_package("my_package").is_some());
+
+    assert!(pm.remove_package("my_package").expect("cannot remove package.").is_some());
+    assert!(!folder.join("my_package.json").exists());
+    assert!(pm.get_package("my_package").is_none());
+
+    assert!(pm.remove_package("built-in").is_err());
+
+    // Packages are saved to and removed from the file they were loaded from.
+    let package = Package {
+        name: "renamed_package".to_string(),
+        version: "1.0.0".to_string(),
+        crates: HashMap::new(),
+        no_std: false,
+    };
+    fs::write(folder.join("other_file.json"), serde_json::to_string(&package).unwrap()).unwrap();
+    assert_eq!(pm.reload_from_folder(), vec!["renamed_package".to_string()]);
+
+    pm.save_package(Package { version: "2.0.0".to_string(), ..package }).expect("cannot save package.");
+    assert!(!folder.join("renamed_package.json").exists());
+    assert!(fs::read_to_string(folder.join("other_file.json")).unwrap().contains("2.0.0"));
+
+    assert!(pm.remove_package("renamed_package").expect("cannot remove package.").is_some());
+    assert!(!folder.join("other_file.json").exists());
+
+    let _ = fs::remove_dir_all(&folder);
+}
+
+#[test]
+fn test_reload_from_folder() {
+    let folder = std::env::temp_dir().join("flowrs_build_test_reload_from_folder");
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).expect("cannot create test folder.");
+
+    let package_json = |version: &str| format!(r#"{{"name": "my_package", "version": "{}", "crates": {{}}}}"#, version);
+    // Modification times can be too coarse to tell two writes apart, so move them apart explicitly.
+    let write = |file_name: &str, content: &str, secs: u64| {
+        let file_path = folder.join(file_name);
+        fs::write(&file_path, content).expect("cannot write package file.");
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+        fs::File::options().write(true).open(&file_path).and_then(|f| f.set_modified(modified)).expect("cannot set modification time.");
+    };
+
+    write("my_package.json", &package_json("1.0.0"), 1);
+    let mut pm = PackageManager::new_from_folder(folder.to_str().unwrap());
+    assert_eq!(pm.get_package("my_package").unwrap().version, "1.0.0");
+    assert!(pm.reload_from_folder().is_empty());
+
+    write("my_package.json", &package_json("2.0.0"), 2);
+    assert_eq!(pm.reload_from_folder(), vec!["my_package".to_string()]);
+    assert_eq!(pm.get_package("my_package").unwrap().version, "2.0.0");
+
+    // A file that fails to parse keeps the previous version and reports the error.
+    write("my_package.json", "{", 3);
+    assert!(pm.reload_from_folder().is_empty());
+    assert_eq!(pm.get_package("my_package").unwrap().version, "2.0.0");
+    assert!(pm.get_load_status()[0].error.is_some());
+
+    // Files saved through the manager while the folder was read are not replaced by the older read.
+    let state = pm.get_package_folder_state().unwrap();
+    write("my_package.json", &package_json("3.0.0"), 4);
+    let contents = state.read().unwrap();
+    pm.save_package(serde_json::from_str(&package_json("4.0.0")).unwrap()).expect("cannot save package.");
+    pm.apply_package_folder(contents);
+    assert_eq!(pm.get_package("my_package").unwrap().version, "4.0.0");
+
+    fs::remove_file(folder.join("my_package.json")).expect("cannot remove package file.");
+    assert_eq!(pm.reload_from_folder(), vec!["my_package".to_string()]);
+    assert!(pm.get_package("my_package").is_none());
+    assert!(pm.get_load_status().is_empty());
+
+    let _ = fs::remove_dir_all(&folder);
+}
@@ -0,0 +1,71 @@
+use flowrs::exec::execution::{Executor, ExecutionContextHandle, ExecutionContext};
+use std::sync::{Arc, Mutex};
+use std::ffi::{ CStr, c_char};
+
+use anyhow::Result;
+
+#[derive(Clone)]
+struct ExecutionContextHandlePtr {
+    ptr: *mut ExecutionContextHandle
+}
+unsafe impl Send for ExecutionContextHandlePtr {}
+
+/// Loads a flow compiled to a shared object and runs it with the given number of workers until it ends or ctrl+C is pressed.
+/// Returns the execution result reported by the flow.
+pub fn run_flow(flow: &str, workers: usize) -> Result<String> {
+
+    // Load the dynamic library
+    println!("-> Load flow from {}.", flow);
+    let lib = libloading::Library::new(flow)
+        .map_err(|err| anyhow::Error::msg(format!("Failed to load the dynamic library '{}': {}", flow, err)))?;
+
+    unsafe {
+
+        let init_func: libloading::Symbol<unsafe extern "C" fn() -> *mut ExecutionContextHandle> = lib.get(b"native_init")?;
+        let run_func: libloading::Symbol<unsafe extern "C" fn(usize, *mut ExecutionContextHandle) -> *const c_char> = lib.get(b"native_run")?;
+        let free_string_func: libloading::Symbol<unsafe extern "C" fn(*const c_char)> = lib.get(b"native_free_string")?;
+        let last_error_func: libloading::Symbol<unsafe extern "C" fn() -> *const c_char> = lib.get(b"native_last_error")?;
+        //let cancel_func: libloading::Symbol<unsafe extern "C" fn(*mut ExecutionContextHandle)> = lib.get(b"native_cancel").expect("Not load.");
+
+        println!("-> Init flow.");
+
+        let init_ptr = init_func();
+        if init_ptr.is_null() {
+            let error_ptr = last_error_func();
+            if error_ptr.is_null() {
+                return Err(anyhow::Error::msg("Flow initialization failed."));
+            }
+            let error = CStr::from_ptr(error_ptr).to_string_lossy().into_owned();
+            free_string_func(error_ptr);
+            return Err(anyhow::Error::msg(format!("Flow initialization failed: {}", error)));
+        }
+
+        let handle_ptr = Arc::new(Mutex::new(ExecutionContextHandlePtr{ ptr: init_ptr}));
+
+        // TODO: We cannot use cancel_func directly in the handler, since it holds a reference to lib which does not live long enough.
+        // Thus, we cast directly into ExecutionContext and use the executor's controller directly.
+        // The downside of this is that the flow must have been compiled with the same Version of ExecutionContext.
+        let ctx = Box::from_raw(handle_ptr.lock().unwrap().clone().ptr.cast::<ExecutionContext>());
+        ctrlc::set_handler(move || {
+
+            println!("-> Flow execution cancellation requested.");
+
+            ctx.executor.controller().lock().unwrap().cancel();
+
+            // Does not work...
+            //cancel_func(mutex_handle_clone.lock().unwrap().ptr);
+        })?;
+
+        println!("-> Start flow execution.");
+
+        let result_ptr = run_func(workers, handle_ptr.lock().unwrap().ptr);
+        let result = CStr::from_ptr(result_ptr)
+                .to_string_lossy()
+                .into_owned();
+        free_string_func(result_ptr);
+
+        println!("-> Flow execution ended.");
+
+        Ok(result)
+    }
+}
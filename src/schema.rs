@@ -0,0 +1,34 @@
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+
+use crate::flow_model::FlowModel;
+use crate::flow_project::FlowProject;
+use crate::package::Package;
+
+/// Kinds of documents a JSON schema can be requested for.
+pub const SCHEMA_KINDS: [&str; 3] = ["package", "project", "flow"];
+
+/// Returns the JSON schema of the serde representation of a package, a flow project or a flow.
+pub fn get_schema(kind: &str) -> Option<RootSchema> {
+    match kind {
+        "package" => Some(schema_for!(Package)),
+        "project" => Some(schema_for!(FlowProject)),
+        "flow" => Some(schema_for!(FlowModel)),
+        _ => Option::None,
+    }
+}
+
+#[test]
+fn test_get_schema() {
+    for kind in SCHEMA_KINDS {
+        let schema = get_schema(kind).expect("schema not available.");
+        assert!(serde_json::to_string(&schema).is_ok());
+    }
+
+    let package_schema = get_schema("package").unwrap();
+    assert!(package_schema.definitions.contains_key("Constructor"));
+    assert!(package_schema.definitions.contains_key("ArgumentType"));
+    assert!(package_schema.definitions.contains_key("ArgumentConstruction"));
+
+    assert!(get_schema("unknown").is_none());
+}
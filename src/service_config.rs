@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+use std::fs::File;
+use std::io::Read;
+
+use crate::flow_project::FlowProjectManagerConfig;
+
+/// Configuration shared by the service and the command-line front end.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ServiceConfig {
+    #[serde(default = "flow_project_manager_config_default")]
+    pub flow_project_manager_config: FlowProjectManagerConfig,
+
+    #[serde(default = "flow_packages_folder_default")]
+    pub flow_packages_folder: String,  
+
+    /// Polling interval for reloading changed package files. 0 disables reloading.
+    #[serde(default = "package_reload_interval_secs_default")]
+    pub package_reload_interval_secs: u64,
+}
+
+fn flow_project_manager_config_default() -> FlowProjectManagerConfig {
+    FlowProjectManagerConfig::default()
+}
+
+fn flow_packages_folder_default() -> String {
+    "flow-packages".to_string()
+}
+
+const fn package_reload_interval_secs_default() -> u64 {
+    2
+}
+
+impl Default for ServiceConfig {
+    fn default() -> Self {
+        Self {
+            flow_project_manager_config: flow_project_manager_config_default(),
+            flow_packages_folder: flow_packages_folder_default(),
+            package_reload_interval_secs: package_reload_interval_secs_default()
+        }
+    }
+}
+
+/// Loads the config file or returns the default config if the file does not exist.
+pub fn load_config(config_path: &str) -> Result<ServiceConfig, anyhow::Error> {
+    
+    if std::path::Path::new(config_path).exists() {
+        // Read and deserialize the existing config.json file
+        let mut file = File::open(config_path)
+            .map_err(|err| anyhow::Error::msg(format!("Failed to open {}: {}", config_path, err)))?;
+        let mut config_content = String::new();
+        file.read_to_string(&mut config_content)
+            .map_err(|err| anyhow::Error::msg(format!("Failed to read {}: {}", config_path, err)))?;
+        serde_json::from_str(&config_content)
+            .map_err(|err| anyhow::Error::msg(format!("Failed to deserialize from {}: {}", config_path, err)))
+    } else {
+        // If the file doesn't exist, create a new FlowProjectManagerConfig with default values.
+        println!("-> Could not read config file '{}'. Creating default config.", config_path);
+        Ok(ServiceConfig::default())
+    }
+}